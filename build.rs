@@ -3,7 +3,13 @@ extern crate rustc_version;
 use rustc_version::{version, Version};
 
 fn main() {
-    if version().unwrap() >= Version::parse("1.77.0").unwrap() {
+    println!("cargo:rustc-check-cfg=cfg(has_offset_of)");
+    println!("cargo:rustc-check-cfg=cfg(has_strict_provenance)");
+    let version = version().unwrap();
+    if version >= Version::parse("1.77.0").unwrap() {
         println!("cargo:rustc-cfg=has_offset_of");
     }
+    if version >= Version::parse("1.84.0").unwrap() {
+        println!("cargo:rustc-cfg=has_strict_provenance");
+    }
 }
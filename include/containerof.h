@@ -0,0 +1,26 @@
+/*
+ * C declarations for the `ffi` feature of the `containerof` crate.
+ *
+ * An `IntrusiveAlias` is a transparent wrapper around an untyped
+ * pointer to an intrusive field, so on the C side it is just a
+ * `const void *`.
+ */
+#ifndef CONTAINEROF_H
+#define CONTAINEROF_H
+
+#include <stddef.h>
+
+typedef const void *containerof_alias;
+
+containerof_alias containerof_alias_from_ptr(const void *ptr);
+const void *containerof_alias_to_ptr(containerof_alias alias);
+
+/*
+ * Given a pointer to the `member` field of a `type`, yield a pointer
+ * to the enclosing `type`. This is the same offset convention used by
+ * `containerof_field_offset!` and `container_of!` on the Rust side.
+ */
+#define containerof_container_of(ptr, type, member) \
+    ((type *)((char *)(ptr) - offsetof(type, member)))
+
+#endif /* CONTAINEROF_H */
@@ -0,0 +1,23 @@
+//! C-callable helpers for passing `IntrusiveAlias` values across an
+//! FFI boundary. `include/containerof.h` declares these functions,
+//! along with a `containerof_container_of` macro that matches the
+//! crate's offset convention.
+//!
+//! `IntrusiveAlias` is `#[repr(transparent)]` over `*const ()`, so C
+//! code sees it as a plain `const void *`.
+
+use core::ffi::c_void;
+use IntrusiveAlias;
+
+/// Create an `IntrusiveAlias` from a pointer to an intrusive field.
+#[no_mangle]
+pub extern "C" fn containerof_alias_from_ptr(ptr: *const c_void) -> IntrusiveAlias {
+    IntrusiveAlias::new(ptr.cast())
+}
+
+/// Get back the pointer to the intrusive field from an
+/// `IntrusiveAlias`.
+#[no_mangle]
+pub extern "C" fn containerof_alias_to_ptr(alias: IntrusiveAlias) -> *const c_void {
+    alias.get_address().cast()
+}
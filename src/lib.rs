@@ -25,11 +25,61 @@
 //! }
 //! containerof_intrusive!(ContainerLink = Container:link::Link);
 //! ```
+//!
+//! # Features
+//!
+//! * `std` (default): link against the standard library. Implies
+//!   `alloc`. Without it, the crate is `no_std`.
+//! * `alloc`: enable the parts of `OwnBox` that interact with the
+//!   global allocator (`OwnBox::new`, `from_box`, `into_box`, and the
+//!   `Rc`/`Arc` conversions), and the `Reclaim` guard.
+//! * `strict_linearity`: panic when an `OwnBox` is dropped, even in
+//!   release builds. Without `std`, debug builds only panic with
+//!   this feature, since they cannot tell whether they are already
+//!   unwinding; a drop during unwinding then aborts the process.
+//! * `allocator_api` (nightly only): convert between `OwnBox` and
+//!   boxes allocated by a custom `Allocator`.
+//! * `ffi`: export C-callable `IntrusiveAlias` helpers (see the `ffi`
+//!   module and `include/containerof.h`).
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate core;
+
+#[cfg(feature = "allocator_api")]
+use alloc::alloc::Allocator;
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::rc::Rc;
+#[cfg(feature = "alloc")]
+use alloc::sync::Arc;
+use core::alloc::Layout;
+use core::cmp;
+use core::convert;
+use core::fmt;
+use core::hash;
+use core::marker;
+use core::mem;
+use core::ops;
+use core::pin::Pin;
+use core::ptr::{self, NonNull};
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod validate;
 
-use std::convert;
-use std::marker;
-use std::mem;
-use std::ops;
+#[doc(hidden)]
+pub use core::convert as __convert;
+#[doc(hidden)]
+pub use core::mem as __mem;
+#[doc(hidden)]
+pub use core::ptr as __ptr;
 
 #[cfg(has_offset_of)]
 /// Implement C-like `offsetof` macro in Rust. Rust has stabilized
@@ -37,7 +87,7 @@ use std::ops;
 #[macro_export]
 macro_rules! containerof_field_offset {
     ($container:ty : $field:ident) => {
-        ::std::mem::offset_of!($container, $field)
+        $crate::__mem::offset_of!($container, $field)
     };
 }
 
@@ -46,13 +96,78 @@ macro_rules! containerof_field_offset {
 /// ::std::mem::offset_of! since 1.77.0, you should use that, instead.
 #[macro_export]
 macro_rules! containerof_field_offset {
-    ($container:ty : $field:ident) => {
-        unsafe { &(*(0usize as *const $container)).$field as *const _ as usize }
+    ($container:ty : $field:ident) => {{
+        // measure against real (if uninitialized) storage, rather
+        // than dereferencing a null pointer.
+        let uninit = $crate::__mem::MaybeUninit::<$container>::uninit();
+        let base = uninit.as_ptr();
+        let field = unsafe { $crate::__ptr::addr_of!((*base).$field) };
+        field as usize - base as usize
+    }};
+}
+
+/// Implement C-like `container_of` macro in Rust: given a pointer to
+/// the `$field` field of a `$container`, yield a `*const $container`
+/// pointing to the enclosing structure. Useful at FFI boundaries,
+/// where defining an `Intrusive` translation type is overkill.
+///
+/// The expansion performs unchecked pointer arithmetic, so it must be
+/// invoked from an `unsafe` block, and `$ptr` must really point to
+/// the `$field` of a `$container`.
+#[macro_export]
+macro_rules! container_of {
+    ($ptr:expr, $container:ty, $field:ident) => {
+        ($ptr as *const u8).sub(containerof_field_offset!($container : $field)) as *const $container
+    };
+}
+
+/// Mutable variant of `container_of!`, yielding a `*mut $container`
+/// from a `*mut` pointer to the `$field` field.
+#[macro_export]
+macro_rules! container_of_mut {
+    ($ptr:expr, $container:ty, $field:ident) => {
+        ($ptr as *mut u8).sub(containerof_field_offset!($container : $field)) as *mut $container
     };
 }
 
 /// Define a type representing the translation between an intrusive
 /// field and its containing structure.
+///
+/// The generated type also implements `AsRef`/`AsMut` for both the
+/// container and the field type, delegating to `as_container` and
+/// `as_field`. (As a consequence, the container and field types must
+/// differ, or the generated impls will conflict.) It converts from and
+/// into `OwnBox<Container>` via `From`, delegating to
+/// `from_container` and `into_container`.
+///
+/// The declared field type is checked against the actual type of the
+/// field at compile time, so a mismatch is a type error rather than
+/// undefined behavior at runtime:
+///
+/// ```compile_fail,E0308
+/// # #[macro_use]
+/// # extern crate containerof;
+/// # use containerof::*;
+/// struct Container {
+///     link: u32,
+/// }
+/// containerof_intrusive!(ContainerLink = Container:link::u64);
+/// # fn main() {}
+/// ```
+///
+/// This holds even where a reference to the field would coerce to
+/// the declared type:
+///
+/// ```compile_fail,E0308
+/// # #[macro_use]
+/// # extern crate containerof;
+/// # use containerof::*;
+/// struct Container {
+///     link: Box<u64>,
+/// }
+/// containerof_intrusive!(ContainerLink = Container:link::u64);
+/// # fn main() {}
+/// ```
 #[macro_export]
 macro_rules! containerof_intrusive {
     ($nt:ident = $container:ty : $field:ident :: $fieldtype:ty) => (
@@ -87,6 +202,56 @@ macro_rules! containerof_intrusive {
             fn as_alias(&self) -> &IntrusiveAlias {
                 unsafe { &*(self as *const _).cast() }
             }
+            #[inline]
+            fn as_alias_mut(&mut self) -> &mut $crate::IntrusiveAlias {
+                unsafe { &mut *(self as *mut _).cast() }
+            }
+        }
+        impl $nt {
+            // fails to compile if `$field` is not a `$fieldtype`. raw
+            // pointers are used since, unlike references, they are
+            // never deref-coerced (e.g. from `&Box<T>` to `&T`).
+            #[allow(dead_code)]
+            #[doc(hidden)]
+            fn __containerof_check_field_type(c: *const $container) -> *const $fieldtype {
+                unsafe { $crate::__ptr::addr_of!((*c).$field) }
+            }
+        }
+        impl $crate::__convert::AsRef<$container> for $nt {
+            #[inline]
+            fn as_ref(&self) -> &$container {
+                $crate::Intrusive::as_container(self)
+            }
+        }
+        impl $crate::__convert::AsMut<$container> for $nt {
+            #[inline]
+            fn as_mut(&mut self) -> &mut $container {
+                $crate::Intrusive::as_container_mut(self)
+            }
+        }
+        impl $crate::__convert::AsRef<$fieldtype> for $nt {
+            #[inline]
+            fn as_ref(&self) -> &$fieldtype {
+                $crate::Intrusive::as_field(self)
+            }
+        }
+        impl $crate::__convert::AsMut<$fieldtype> for $nt {
+            #[inline]
+            fn as_mut(&mut self) -> &mut $fieldtype {
+                $crate::Intrusive::as_field_mut(self)
+            }
+        }
+        impl $crate::__convert::From<$crate::OwnBox<$container>> for $nt {
+            #[inline]
+            fn from(c: $crate::OwnBox<$container>) -> $nt {
+                $crate::Intrusive::from_container(c)
+            }
+        }
+        impl $crate::__convert::From<$nt> for $crate::OwnBox<$container> {
+            #[inline]
+            fn from(nt: $nt) -> $crate::OwnBox<$container> {
+                $crate::Intrusive::into_container(nt)
+            }
         }
         );
 }
@@ -97,13 +262,31 @@ macro_rules! containerof_intrusive {
 /// the facility to use the single (but type-unsafe) IntrusiveAlias
 /// type, while allowing type-safe wrapper implementations to delegate
 /// their behavior to the implementation function.
-#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+///
+/// Comparison and hashing use the address of the alias, so aliases
+/// may be used as identity keys in ordered or hashed collections.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone, Debug)]
+#[repr(transparent)]
 pub struct IntrusiveAlias(pub *const ());
 impl IntrusiveAlias {
     /// Create an IntrusiveAlias instance from a pointer address.
     pub fn new(addr: *const ()) -> IntrusiveAlias {
         IntrusiveAlias(addr)
     }
+    /// Create an IntrusiveAlias instance from a pointer address,
+    /// returning `None` if the address is null.
+    pub fn new_checked(addr: *const ()) -> Option<IntrusiveAlias> {
+        if addr.is_null() {
+            None
+        } else {
+            Some(IntrusiveAlias::new(addr))
+        }
+    }
+    /// Create an IntrusiveAlias instance with a null address. A null
+    /// alias must never be dereferenced.
+    pub fn null() -> IntrusiveAlias {
+        IntrusiveAlias::new(ptr::null())
+    }
     /// Create an IntrusiveAlias instance which points to a borrowed
     /// pointer.
     pub fn new_of<T>(addr: &T) -> IntrusiveAlias {
@@ -114,48 +297,341 @@ impl IntrusiveAlias {
     pub fn get_address(&self) -> *const () {
         self.0
     }
+    /// Create an IntrusiveAlias instance whose address is `bytes`
+    /// bytes away from this one. The arithmetic wraps on overflow, and
+    /// the result carries the same provenance as `self`.
+    pub fn offset_by(self, bytes: isize) -> IntrusiveAlias {
+        IntrusiveAlias::new(self.0.cast::<u8>().wrapping_offset(bytes).cast())
+    }
+    /// Create an IntrusiveAlias instance whose address is `bytes`
+    /// bytes above this one. (Wrapping, like `offset_by`.)
+    pub fn byte_add(self, bytes: usize) -> IntrusiveAlias {
+        IntrusiveAlias::new(self.0.cast::<u8>().wrapping_add(bytes).cast())
+    }
+    /// Create an IntrusiveAlias instance whose address is `bytes`
+    /// bytes below this one. (Wrapping, like `offset_by`.)
+    pub fn byte_sub(self, bytes: usize) -> IntrusiveAlias {
+        IntrusiveAlias::new(self.0.cast::<u8>().wrapping_sub(bytes).cast())
+    }
+    /// Create an IntrusiveAlias instance that stores `tag` in the
+    /// low bits of this alias's address, which are unused when the
+    /// alias points to a `T`. `tag` must be less than
+    /// `align_of::<T>()`, and `self` must be an untagged alias,
+    /// aligned for `T`.
+    pub fn with_tag<T>(self, tag: usize) -> IntrusiveAlias {
+        let mask = mem::align_of::<T>() - 1;
+        assert!(
+            tag & !mask == 0,
+            "tag does not fit in the alignment bits of the pointee"
+        );
+        assert!(
+            self.addr() & mask == 0,
+            "cannot tag an alias that is not aligned for the pointee"
+        );
+        self.byte_add(tag)
+    }
+    /// Get the tag stored in the low bits of this alias by `with_tag`.
+    pub fn tag<T>(&self) -> usize {
+        self.addr() & (mem::align_of::<T>() - 1)
+    }
+    /// Create an IntrusiveAlias instance with the tag stored by
+    /// `with_tag` cleared, suitable for dereferencing as a `T`.
+    pub fn untagged<T>(&self) -> IntrusiveAlias {
+        self.byte_sub(self.tag::<T>())
+    }
+    /// Get the address of the alias as an integer, for comparison or
+    /// alignment checks. The integer carries no provenance, so it must
+    /// not be cast back into a pointer; derive new aliases from
+    /// existing ones (e.g. with `byte_add`) instead.
+    #[cfg(has_strict_provenance)]
+    pub fn addr(&self) -> usize {
+        self.0.addr()
+    }
+    /// Get the address of the alias as an integer, for comparison or
+    /// alignment checks. The integer carries no provenance, so it must
+    /// not be cast back into a pointer; derive new aliases from
+    /// existing ones (e.g. with `byte_add`) instead.
+    #[cfg(not(has_strict_provenance))]
+    pub fn addr(&self) -> usize {
+        self.0 as usize
+    }
+    /// Returns `true` if the alias has a null address.
+    pub fn is_null(&self) -> bool {
+        self.0.is_null()
+    }
+    /// Get the address of the alias as a typed pointer.
+    pub fn cast<T>(&self) -> *const T {
+        self.0.cast()
+    }
+    /// Get the address of the alias as a typed mutable pointer.
+    pub fn cast_mut<T>(&self) -> *mut T {
+        self.0 as *mut T
+    }
+}
+impl<T> convert::From<NonNull<T>> for IntrusiveAlias {
+    fn from(pointer: NonNull<T>) -> IntrusiveAlias {
+        IntrusiveAlias::new(pointer.as_ptr() as *const ())
+    }
+}
+impl<T> convert::TryFrom<IntrusiveAlias> for NonNull<T> {
+    type Error = NullAliasError;
+    fn try_from(alias: IntrusiveAlias) -> Result<NonNull<T>, NullAliasError> {
+        NonNull::new(alias.cast_mut()).ok_or(NullAliasError)
+    }
+}
+
+/// Error returned when converting a null `IntrusiveAlias` into a
+/// non-null pointer type.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct NullAliasError;
+impl fmt::Display for NullAliasError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("intrusive alias is null")
+    }
+}
+#[cfg(feature = "std")]
+impl std::error::Error for NullAliasError {}
+
+/// An `IntrusiveAlias` that is known not to be null. Because of the
+/// null-pointer niche, `Option<NonNullIntrusiveAlias>` is the same
+/// size as a pointer, which makes it the preferred representation for
+/// optional link fields (e.g. `next: Option<NonNullIntrusiveAlias>`).
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone, Debug)]
+#[repr(transparent)]
+pub struct NonNullIntrusiveAlias(NonNull<()>);
+impl NonNullIntrusiveAlias {
+    /// Create a NonNullIntrusiveAlias instance from a non-null pointer.
+    pub fn new(addr: NonNull<()>) -> NonNullIntrusiveAlias {
+        NonNullIntrusiveAlias(addr)
+    }
+    /// Create a NonNullIntrusiveAlias instance from an IntrusiveAlias,
+    /// returning `None` if the alias is null.
+    pub fn from_alias(alias: IntrusiveAlias) -> Option<NonNullIntrusiveAlias> {
+        NonNull::new(alias.get_address() as *mut ()).map(NonNullIntrusiveAlias)
+    }
+    /// Create a NonNullIntrusiveAlias instance which points to a
+    /// borrowed pointer.
+    pub fn new_of<T>(addr: &T) -> NonNullIntrusiveAlias {
+        NonNullIntrusiveAlias(NonNull::from(addr).cast())
+    }
+    /// Get back the pointer address from which the
+    /// `NonNullIntrusiveAlias` was constructed.
+    pub fn get_address(&self) -> *const () {
+        self.0.as_ptr()
+    }
+    /// Return a borrow-pointer of an IntrusiveAlias with the same
+    /// address, for use with the `Intrusive` translation routines.
+    pub fn as_alias(&self) -> &IntrusiveAlias {
+        // both types are transparent wrappers around a thin pointer.
+        unsafe { &*(self as *const NonNullIntrusiveAlias).cast() }
+    }
+}
+impl convert::From<NonNullIntrusiveAlias> for IntrusiveAlias {
+    fn from(alias: NonNullIntrusiveAlias) -> IntrusiveAlias {
+        IntrusiveAlias::new(alias.get_address())
+    }
+}
+
+/// An `IntrusiveAlias` that can be shared between threads, for use as
+/// the link (or head) field of lock-free intrusive structures. Every
+/// operation mirrors the corresponding `AtomicPtr` operation.
+#[derive(Debug)]
+#[repr(transparent)]
+pub struct AtomicIntrusiveAlias(AtomicPtr<()>);
+impl AtomicIntrusiveAlias {
+    /// Create an AtomicIntrusiveAlias instance holding `alias`.
+    pub fn new(alias: IntrusiveAlias) -> AtomicIntrusiveAlias {
+        AtomicIntrusiveAlias(AtomicPtr::new(alias.get_address() as *mut ()))
+    }
+    /// Create an AtomicIntrusiveAlias instance holding a null alias.
+    pub fn null() -> AtomicIntrusiveAlias {
+        AtomicIntrusiveAlias::new(IntrusiveAlias::null())
+    }
+    /// Load the current alias.
+    pub fn load(&self, order: Ordering) -> IntrusiveAlias {
+        IntrusiveAlias::new(self.0.load(order))
+    }
+    /// Store `alias`, replacing the current alias.
+    pub fn store(&self, alias: IntrusiveAlias, order: Ordering) {
+        self.0.store(alias.get_address() as *mut (), order)
+    }
+    /// Store `alias`, returning the previous alias.
+    pub fn swap(&self, alias: IntrusiveAlias, order: Ordering) -> IntrusiveAlias {
+        IntrusiveAlias::new(self.0.swap(alias.get_address() as *mut (), order))
+    }
+    /// Store `new` if the current alias is `current`. Returns the
+    /// previous alias, wrapped in `Ok` if the store happened.
+    pub fn compare_exchange(
+        &self,
+        current: IntrusiveAlias,
+        new: IntrusiveAlias,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<IntrusiveAlias, IntrusiveAlias> {
+        self.0
+            .compare_exchange(
+                current.get_address() as *mut (),
+                new.get_address() as *mut (),
+                success,
+                failure,
+            )
+            .map(|p| IntrusiveAlias::new(p))
+            .map_err(|p| IntrusiveAlias::new(p))
+    }
+    /// Like `compare_exchange`, but may fail spuriously, which allows
+    /// for more efficient code on some platforms when called in a
+    /// loop.
+    pub fn compare_exchange_weak(
+        &self,
+        current: IntrusiveAlias,
+        new: IntrusiveAlias,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<IntrusiveAlias, IntrusiveAlias> {
+        self.0
+            .compare_exchange_weak(
+                current.get_address() as *mut (),
+                new.get_address() as *mut (),
+                success,
+                failure,
+            )
+            .map(|p| IntrusiveAlias::new(p))
+            .map_err(|p| IntrusiveAlias::new(p))
+    }
+    /// Get a mutable reference to the underlying alias. This is safe
+    /// because the mutable reference guarantees that no other threads
+    /// are concurrently accessing the alias.
+    pub fn get_mut(&mut self) -> &mut IntrusiveAlias {
+        // `IntrusiveAlias` and `AtomicPtr<()>` are both transparent
+        // wrappers around `*mut ()`/`*const ()`.
+        unsafe { &mut *(self.0.get_mut() as *mut *mut ()).cast() }
+    }
+    /// Consume the atomic, returning the contained alias.
+    pub fn into_inner(self) -> IntrusiveAlias {
+        IntrusiveAlias::new(self.0.into_inner())
+    }
 }
 
 /// Represent ownership of an object via ownership of an intrusive
 /// field within the object. Differs from Rust-standard `Box<T>` in
-/// that dropping an `OwnBox<T>` instance is a bug.
+/// that dropping an `OwnBox<T>` instance is a bug: debug builds with
+/// `std` (and builds enabling the `strict_linearity` feature) panic
+/// when an `OwnBox<T>` is dropped without being consumed.
 // FIXME: this wants to be a linear type, but that requires linear-type
 // support in the language.
 pub struct OwnBox<T> {
-    pointer: IntrusiveAlias,
+    pointer: NonNull<T>,
     marker: marker::PhantomData<T>,
 }
 impl<T> OwnBox<T> {
+    /// Allocate `value` on the heap and take ownership of it, in the
+    /// manner of `Box::new`. The result must still be consumed (e.g.
+    /// via `into_box` or `Intrusive::from_container`), or the
+    /// allocation will leak.
+    #[cfg(feature = "alloc")]
+    pub fn new(value: T) -> OwnBox<T> {
+        OwnBox::from_box(Box::new(value))
+    }
     /// Get value pointer address.
     pub fn get_address(&self) -> *const () {
-        self.pointer.0
+        self.pointer.as_ptr() as *const ()
+    }
+    /// Get a typed pointer to the value, without creating an
+    /// intermediate reference.
+    pub fn as_ptr(&self) -> *const T {
+        self.pointer.as_ptr()
+    }
+    /// Get a typed mutable pointer to the value, without creating an
+    /// intermediate reference.
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.pointer.as_ptr()
     }
     /// Construct an OwnBox from an IntrusiveAlias pointer.
     /// # Safety
     /// This creates an "owned" structure from a raw pointer, which is
-    /// unsafe. The caller must ensure that no copies of `pointer` are
-    /// used while `OwnBox` is alive.
+    /// unsafe. The caller must ensure that `pointer` is not null, and
+    /// that no copies of `pointer` are used while `OwnBox` is alive.
+    /// Debug builds panic if `pointer` is not aligned for `T`.
     pub unsafe fn from_alias(pointer: IntrusiveAlias) -> OwnBox<T> {
+        debug_assert!(
+            pointer.addr() & (mem::align_of::<T>() - 1) == 0,
+            "OwnBox::from_alias: address {:p} is not aligned for the target type",
+            pointer.get_address()
+        );
         OwnBox {
-            pointer,
+            pointer: NonNull::new_unchecked(pointer.get_address() as *mut T),
             marker: marker::PhantomData,
         }
     }
+    /// Construct an OwnBox from an IntrusiveAlias pointer, returning
+    /// `None` if the pointer is null.
+    /// # Safety
+    /// As with `from_alias`, the caller must ensure that no copies of
+    /// `pointer` are used while `OwnBox` is alive.
+    pub unsafe fn try_from_alias(pointer: IntrusiveAlias) -> Option<OwnBox<T>> {
+        if pointer.is_null() {
+            None
+        } else {
+            Some(OwnBox::from_alias(pointer))
+        }
+    }
     /// Move ownership of an OwnBox into an IntrusiveAlias pointer.
+    ///
+    /// The OwnBox is consumed, so it cannot be used afterward:
+    ///
+    /// ```compile_fail,E0382
+    /// # extern crate containerof;
+    /// # use containerof::*;
+    /// # fn main() {
+    /// let ob = OwnBox::new(1u32);
+    /// let alias = ob.into_alias();
+    /// assert_eq!(1, *ob);
+    /// # let _ = unsafe { OwnBox::<u32>::from_alias(alias).into_box() };
+    /// # }
+    /// ```
     pub fn into_alias(self) -> IntrusiveAlias {
-        let rval = self.pointer;
+        let rval = IntrusiveAlias::new(self.get_address());
+        mem::forget(self);
+        rval
+    }
+    /// Consume the OwnBox, returning a mutable reference to the value
+    /// that lives as long as the caller likes, in the manner of
+    /// `Box::leak`. The value will never be reclaimed.
+    pub fn leak<'a>(self) -> &'a mut T
+    where
+        T: 'a,
+    {
+        let mut pointer = self.pointer;
+        mem::forget(self);
+        unsafe { pointer.as_mut() }
+    }
+    /// Reinterpret ownership of the value as ownership of a `U` at the
+    /// same address, without touching memory.
+    /// # Safety
+    /// `T` and `U` must be layout-compatible: the value must be a
+    /// valid `U`, with the same size and alignment as `T`, so that
+    /// the storage can later be reclaimed as a `U` (e.g. by
+    /// `into_box`) exactly as it would have been as a `T`.
+    pub unsafe fn cast<U>(self) -> OwnBox<U> {
+        let rval = OwnBox {
+            pointer: self.pointer.cast(),
+            marker: marker::PhantomData,
+        };
         mem::forget(self);
         rval
     }
     /// Return a borrow-pointer of an IntrusiveAlias with same address
     /// as the OwnBox.
     pub fn as_alias(&self) -> &IntrusiveAlias {
-        &self.pointer
+        // `IntrusiveAlias` is a transparent wrapper around a thin
+        // pointer, so it shares the representation of `NonNull<T>`.
+        unsafe { &*(&self.pointer as *const NonNull<T>).cast() }
     }
     /// Construct an OwnBox from a Box.
+    #[cfg(feature = "alloc")]
     pub fn from_box(b: Box<T>) -> OwnBox<T> {
         OwnBox {
-            pointer: IntrusiveAlias::new(unsafe { mem::transmute(b) }),
+            pointer: unsafe { NonNull::new_unchecked(Box::into_raw(b)) },
             marker: marker::PhantomData,
         }
     }
@@ -166,38 +642,273 @@ impl<T> OwnBox<T> {
     /// The caller must ensure that `self` was originally constructed
     /// from a `Box`. If it was not, then dropping the resulting Box
     /// will result in an attempt to free an invalid pointer.
+    #[cfg(feature = "alloc")]
     pub unsafe fn into_box(self) -> Box<T> {
-        mem::transmute(self.into_alias().get_address())
+        Box::from_raw(self.into_alias().get_address() as *mut T)
+    }
+}
+/// Deferred initialization, mirroring `Box<MaybeUninit<T>>`.
+impl<T> OwnBox<mem::MaybeUninit<T>> {
+    /// Initialize the value in place, returning ownership of the
+    /// now-initialized `T`.
+    pub fn write(mut self, value: T) -> OwnBox<T> {
+        unsafe {
+            self.as_mut_ptr().cast::<T>().write(value);
+            self.assume_init()
+        }
+    }
+    /// Treat the value as initialized.
+    /// # Safety
+    /// As with `MaybeUninit::assume_init`, the value must actually be
+    /// initialized.
+    pub unsafe fn assume_init(self) -> OwnBox<T> {
+        // `MaybeUninit<T>` has the same layout as `T`.
+        self.cast()
+    }
+}
+/// Conversions to and from shared ownership.
+///
+/// `Rc` and `Arc` store their reference counts in a header ahead of
+/// the value, so an `Rc`-allocated value cannot be reclaimed as a
+/// `Box` (nor the reverse). Rather than have an `OwnBox` point into
+/// an `Rc` allocation, these conversions move the value between
+/// allocations: the `OwnBox` always owns a plain `Box` allocation,
+/// and `into_box` remains the only way to reclaim it. Pointers
+/// obtained from `Rc::into_raw` / `Arc::into_raw` must never be
+/// passed to `OwnBox::from_alias`, since `into_box` would then free
+/// an address the global allocator never handed out.
+#[cfg(feature = "alloc")]
+impl<T> OwnBox<T> {
+    /// Move the value into a new `Rc`, releasing the `OwnBox`
+    /// allocation.
+    /// # Safety
+    /// As with `into_box`, `self` must have been constructed from a
+    /// `Box`.
+    pub unsafe fn into_rc(self) -> Rc<T> {
+        Rc::new(*self.into_box())
+    }
+    /// Take the value out of `rc` into a new `OwnBox`, if `rc` is the
+    /// only strong reference. Otherwise `rc` is handed back
+    /// unchanged.
+    pub fn from_rc(rc: Rc<T>) -> Result<OwnBox<T>, Rc<T>> {
+        Rc::try_unwrap(rc).map(OwnBox::new)
+    }
+    /// Move the value into a new `Arc`, releasing the `OwnBox`
+    /// allocation.
+    /// # Safety
+    /// As with `into_box`, `self` must have been constructed from a
+    /// `Box`.
+    pub unsafe fn into_arc(self) -> Arc<T> {
+        Arc::new(*self.into_box())
+    }
+    /// Take the value out of `arc` into a new `OwnBox`, if `arc` is
+    /// the only strong reference. Otherwise `arc` is handed back
+    /// unchanged.
+    pub fn from_arc(arc: Arc<T>) -> Result<OwnBox<T>, Arc<T>> {
+        Arc::try_unwrap(arc).map(OwnBox::new)
+    }
+}
+#[cfg(feature = "allocator_api")]
+impl<T> OwnBox<T> {
+    /// Construct an OwnBox from a Box allocated by `A`. The allocator
+    /// is handed back to the caller, who must keep it alive and later
+    /// pass it to `into_box_in`, so that the allocation is returned to
+    /// the allocator that produced it.
+    pub fn from_box_in<A: Allocator>(b: Box<T, A>) -> (OwnBox<T>, A) {
+        let (pointer, alloc) = Box::into_raw_with_allocator(b);
+        let own = OwnBox {
+            pointer: unsafe { NonNull::new_unchecked(pointer) },
+            marker: marker::PhantomData,
+        };
+        (own, alloc)
+    }
+    /// Construct a Box from an OwnBox, using `alloc` to reclaim the
+    /// allocation. (Inverse of `from_box_in`.)
+    /// # Safety
+    /// The caller must ensure that `self` was originally constructed
+    /// by `from_box_in`, and that `alloc` is the allocator (or a
+    /// handle to the allocator) that `from_box_in` returned.
+    pub unsafe fn into_box_in<A: Allocator>(self, alloc: A) -> Box<T, A> {
+        Box::from_raw_in(self.into_alias().get_address() as *mut T, alloc)
     }
 }
+#[cfg(feature = "alloc")]
 impl<T> convert::From<Box<T>> for OwnBox<T> {
     fn from(t: Box<T>) -> OwnBox<T> {
         OwnBox::from_box(t)
     }
 }
+/// Cloning an `OwnBox` clones the value into a fresh global-allocator
+/// `Box`, so the clone must be reclaimed with `into_box` (or handed on
+/// to something that will). This is the right thing for an `OwnBox`
+/// built by `new` or `from_box`; for one that owns storage from some
+/// other source (an arena, a pool, `from_alias` on a foreign pointer),
+/// the clone will not come from that source, which may not be what
+/// the caller wants.
+#[cfg(feature = "alloc")]
+impl<T: Clone> Clone for OwnBox<T> {
+    fn clone(&self) -> OwnBox<T> {
+        OwnBox::new((**self).clone())
+    }
+}
 impl<T> ops::Deref for OwnBox<T> {
     type Target = T;
     fn deref(&self) -> &T {
-        unsafe { &*(self.get_address() as *const _) }
+        unsafe { self.pointer.as_ref() }
     }
 }
 impl<T> ops::DerefMut for OwnBox<T> {
     fn deref_mut(&mut self) -> &mut T {
-        unsafe { &mut *(self.get_address() as *mut T) }
+        unsafe { self.pointer.as_mut() }
+    }
+}
+impl<T: fmt::Debug> fmt::Debug for OwnBox<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OwnBox")
+            .field("address", &self.get_address())
+            .field("value", &**self)
+            .finish()
+    }
+}
+/// `OwnBox` equality is pointer identity: two `OwnBox`es are equal
+/// if they refer to the same address, regardless of the values they
+/// point to.
+impl<T> cmp::PartialEq for OwnBox<T> {
+    fn eq(&self, other: &OwnBox<T>) -> bool {
+        self.get_address() == other.get_address()
+    }
+}
+impl<T> cmp::Eq for OwnBox<T> {}
+/// `OwnBox` ordering compares addresses, not pointed-to values.
+impl<T> cmp::PartialOrd for OwnBox<T> {
+    fn partial_cmp(&self, other: &OwnBox<T>) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<T> cmp::Ord for OwnBox<T> {
+    fn cmp(&self, other: &OwnBox<T>) -> cmp::Ordering {
+        self.get_address().cmp(&other.get_address())
+    }
+}
+/// `OwnBox` hashes its address, consistent with its `PartialEq`.
+impl<T> hash::Hash for OwnBox<T> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.get_address().hash(state)
     }
 }
 impl<T> ops::Drop for OwnBox<T> {
     fn drop(&mut self) {
         // should have been consumed via "into_box" or "into_alias".
         // TODO: want a better way to encourage linearity on this type.
-        //panic!("containerof::OwnBox should be treated as linear!");
+        // debug builds (or builds with the `strict_linearity` feature)
+        // treat a dropped OwnBox as the bug it is; release builds leak
+        // the pointee. don't panic while already unwinding, though,
+        // since that would abort the process. without `std` there is
+        // no way to tell, so only `strict_linearity` panics there, and
+        // accepts that risk.
+        if cfg!(any(
+            all(debug_assertions, feature = "std"),
+            feature = "strict_linearity"
+        )) && !panicking()
+        {
+            panic!("OwnBox must be consumed via into_box/into_container/into_field");
+        }
+    }
+}
+#[cfg(feature = "std")]
+fn panicking() -> bool {
+    ::std::thread::panicking()
+}
+#[cfg(not(feature = "std"))]
+fn panicking() -> bool {
+    false
+}
+// `OwnBox<T>` uniquely owns its pointee, exactly as `Box<T>` does, so
+// it may be sent or shared across threads whenever `T` may be. The
+// `OwnBox<Field>` returned by `Intrusive::into_field` is the exception:
+// it still owns the whole container, which may be `!Send` even when
+// the field is `Send`. That is why `from_field` requires that such a
+// box not have crossed threads unless the container is `Send`.
+unsafe impl<T: Send> Send for OwnBox<T> {}
+unsafe impl<T: Sync> Sync for OwnBox<T> {}
+
+/// A guard that frees a box-derived `OwnBox` when it goes out of
+/// scope, bridging `OwnBox` back to ordinary RAII once the caller has
+/// decided that the value's life ends with the guard's.
+///
+/// Dropping the guard reclaims the storage with `into_box`, so this is
+/// only sound under the same contract: the `OwnBox`'s storage must
+/// have come from a global-allocator `Box`. That holds however the
+/// `OwnBox` was reached, e.g. directly from `OwnBox::new` or
+/// `from_box`, or via `Intrusive::into_container` of an intrusive
+/// pointer built from one. As with `into_box`, nothing checks this.
+#[cfg(feature = "alloc")]
+pub struct Reclaim<T> {
+    inner: Option<OwnBox<T>>,
+}
+#[cfg(feature = "alloc")]
+impl<T> Reclaim<T> {
+    /// Take responsibility for freeing `ob`.
+    /// # Safety
+    /// As with `OwnBox::into_box`, `ob` must have been constructed
+    /// from a `Box`.
+    pub unsafe fn new(ob: OwnBox<T>) -> Reclaim<T> {
+        Reclaim { inner: Some(ob) }
+    }
+    /// Disarm the guard, handing back the `OwnBox` without freeing
+    /// it.
+    pub fn into_inner(mut self) -> OwnBox<T> {
+        self.inner.take().unwrap()
+    }
+}
+#[cfg(feature = "alloc")]
+impl<T> ops::Deref for Reclaim<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.inner.as_ref().unwrap()
+    }
+}
+#[cfg(feature = "alloc")]
+impl<T> ops::DerefMut for Reclaim<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.inner.as_mut().unwrap()
+    }
+}
+#[cfg(feature = "alloc")]
+impl<T> ops::Drop for Reclaim<T> {
+    fn drop(&mut self) {
+        if let Some(ob) = self.inner.take() {
+            // `new` requires that `ob` be box-derived, exactly as
+            // `into_box` does.
+            drop(unsafe { ob.into_box() });
+        }
     }
 }
 
 /// A borrow-pointer that does not require explicit ownership of the
 /// value being borrowed. Used to allow construction of the Intrusive
 /// structure translation type from a borrow pointer.
-#[derive(Debug)]
+///
+/// A BorrowBox may not outlive the container it borrows:
+///
+/// ```compile_fail,E0597
+/// # #[macro_use]
+/// # extern crate containerof;
+/// # use containerof::*;
+/// struct Node {
+///     link: Option<IntrusiveAlias>,
+/// }
+/// containerof_intrusive!(NodeLink = Node:link::Option<IntrusiveAlias>);
+///
+/// # fn main() {
+/// let link;
+/// {
+///     let node = Node { link: None };
+///     link = <NodeLink as Intrusive>::of_container(&node);
+/// }
+/// assert!(link.as_field().is_none());
+/// # }
+/// ```
 pub struct BorrowBox<'a, T: 'a> {
     pointer: IntrusiveAlias,
     marker: marker::PhantomData<&'a T>,
@@ -217,12 +928,44 @@ where
     /// pointee is outlived by the `_lifetime` argument, and that no
     /// conflicting references are made to the pointee while the
     /// BorrowBox is alive.
-    pub unsafe fn new_from<U>(pointer: IntrusiveAlias, _lifetime: &'a U) -> BorrowBox<'a, T> {
+    pub unsafe fn new_from<U: ?Sized>(
+        pointer: IntrusiveAlias,
+        _lifetime: &'a U,
+    ) -> BorrowBox<'a, T> {
         BorrowBox {
             pointer,
             marker: marker::PhantomData,
         }
     }
+    /// Get the address of the borrowed field, e.g. to hand to a C
+    /// callback that will later pass it back to `from_raw`.
+    pub fn as_raw(&self) -> *const () {
+        self.pointer.get_address()
+    }
+    /// Rebuild a BorrowBox from an address obtained from `as_raw`,
+    /// with a lifetime taken from `_lifetime`. (Mirrors `new_from`.)
+    /// # Safety
+    /// The caller must ensure that `pointer` came from `as_raw` on a
+    /// `BorrowBox<T>`, that the pointee is still alive and outlives
+    /// the `_lifetime` argument, and that no conflicting references
+    /// are made to it while the BorrowBox is alive.
+    pub unsafe fn from_raw<U: ?Sized>(pointer: *const (), _lifetime: &'a U) -> BorrowBox<'a, T> {
+        BorrowBox::new_from(IntrusiveAlias::new(pointer), _lifetime)
+    }
+    /// Convert this borrow into a borrow of a sibling intrusive field
+    /// `U` within the same container, preserving the lifetime. (Unlike
+    /// `Intrusive::project`, the result is not tied to a borrow of
+    /// `self`.)
+    pub fn map_field<U>(self) -> BorrowBox<'a, U>
+    where
+        U: Intrusive<Container = T::Container>,
+    {
+        let containerptr = container_alias::<T>(self.pointer);
+        BorrowBox {
+            pointer: field_alias::<U>(containerptr),
+            marker: marker::PhantomData,
+        }
+    }
 }
 impl<'a, T> ops::Deref for BorrowBox<'a, T>
 where
@@ -234,12 +977,76 @@ where
         unsafe { Intrusive::of_alias(&self.pointer) }
     }
 }
+impl<'a, T> fmt::Debug for BorrowBox<'a, T>
+where
+    T: Intrusive,
+    T::Container: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BorrowBox")
+            .field("address", &self.pointer.get_address())
+            .field("container", self.as_container())
+            .finish()
+    }
+}
+// `Clone`/`Copy` are implemented by hand, since deriving them would
+// require `T: Clone`/`T: Copy`.
+impl<'a, T> Clone for BorrowBox<'a, T> {
+    fn clone(&self) -> BorrowBox<'a, T> {
+        *self
+    }
+}
+impl<'a, T> Copy for BorrowBox<'a, T> {}
+/// `BorrowBox` equality is pointer identity: two `BorrowBox`es are
+/// equal if they refer to the same address.
+impl<'a, T> cmp::PartialEq for BorrowBox<'a, T> {
+    fn eq(&self, other: &BorrowBox<'a, T>) -> bool {
+        self.pointer == other.pointer
+    }
+}
+impl<'a, T> cmp::Eq for BorrowBox<'a, T> {}
+// `BorrowBox` grants shared access to the container, like a
+// `&'a T::Container`, so it is `Send` and `Sync` only when the
+// container is `Sync`.
+unsafe impl<'a, T> Send for BorrowBox<'a, T>
+where
+    T: Intrusive,
+    T::Container: Sync,
+{
+}
+unsafe impl<'a, T> Sync for BorrowBox<'a, T>
+where
+    T: Intrusive,
+    T::Container: Sync,
+{
+}
 
 /// A mutable borrow-pointer that does not require explicit ownership
 /// of the value being borrowed. Used to allow construction of the
 /// Intrusive structure translation type from a mutable borrow
 /// pointer.
-#[derive(Debug)]
+///
+/// As with `&mut`, only one BorrowBoxMut of a container may be alive
+/// at a time:
+///
+/// ```compile_fail,E0499
+/// # #[macro_use]
+/// # extern crate containerof;
+/// # use containerof::*;
+/// struct Node {
+///     value: u32,
+///     link: Option<IntrusiveAlias>,
+/// }
+/// containerof_intrusive!(NodeLink = Node:link::Option<IntrusiveAlias>);
+///
+/// # fn main() {
+/// let mut node = Node { value: 0, link: None };
+/// let mut first = <NodeLink as Intrusive>::of_container_mut(&mut node);
+/// let mut second = <NodeLink as Intrusive>::of_container_mut(&mut node);
+/// first.as_container_mut().value = 1;
+/// second.as_container_mut().value = 2;
+/// # }
+/// ```
 pub struct BorrowBoxMut<'a, T: 'a>
 where
     T: Intrusive,
@@ -262,7 +1069,7 @@ where
     /// that the lifetime of the pointee is outlived by the `_lifetime`
     /// argument, and that no conflicting references are made to the
     /// pointee while the BorrowBoxMut is alive.
-    pub unsafe fn new_from<U>(
+    pub unsafe fn new_from<U: ?Sized>(
         pointer: IntrusiveAlias,
         _lifetime: &'a mut U,
     ) -> BorrowBoxMut<'a, T> {
@@ -271,6 +1078,17 @@ where
             marker: marker::PhantomData,
         }
     }
+    /// Produce a shorter-lived mutable borrow from this one, in the
+    /// manner of an implicit `&mut` reborrow. This one becomes usable
+    /// again once the reborrow is dropped.
+    pub fn reborrow(&mut self) -> BorrowBoxMut<'_, T> {
+        let pointer = self.pointer;
+        unsafe { BorrowBoxMut::new_from(pointer, self) }
+    }
+    /// Produce a shared borrow from this mutable one.
+    pub fn as_borrow(&self) -> BorrowBox<'_, T> {
+        unsafe { BorrowBox::new_from(self.pointer, self) }
+    }
 }
 impl<'a, T> ops::Deref for BorrowBoxMut<'a, T>
 where
@@ -290,6 +1108,163 @@ where
         unsafe { Intrusive::of_alias_mut(&mut self.pointer) }
     }
 }
+impl<'a, T> fmt::Debug for BorrowBoxMut<'a, T>
+where
+    T: Intrusive,
+    T::Container: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BorrowBoxMut")
+            .field("address", &self.pointer.get_address())
+            .field("container", self.as_container())
+            .finish()
+    }
+}
+/// `BorrowBoxMut` equality is pointer identity, as for `BorrowBox`.
+impl<'a, T: Intrusive> cmp::PartialEq for BorrowBoxMut<'a, T> {
+    fn eq(&self, other: &BorrowBoxMut<'a, T>) -> bool {
+        self.pointer == other.pointer
+    }
+}
+impl<'a, T: Intrusive> cmp::Eq for BorrowBoxMut<'a, T> {}
+// `BorrowBoxMut` grants exclusive access to the container, like a
+// `&'a mut T::Container`, so it is `Send` when the container is
+// `Send`, and `Sync` when the container is `Sync`.
+unsafe impl<'a, T> Send for BorrowBoxMut<'a, T>
+where
+    T: Intrusive,
+    T::Container: Send,
+{
+}
+unsafe impl<'a, T> Sync for BorrowBoxMut<'a, T>
+where
+    T: Intrusive,
+    T::Container: Sync,
+{
+}
+
+/// A non-owning, lifetime-erased handle to an intrusive field of type
+/// `T`, for storing references inside structures (such as graphs with
+/// cycles) where a real lifetime cannot be expressed. A `WeakAlias`
+/// makes no claim that its target is still alive: the user is
+/// responsible for discarding it when the target goes away, and must
+/// promise as much when calling `upgrade`.
+pub struct WeakAlias<T> {
+    pointer: IntrusiveAlias,
+    marker: marker::PhantomData<*const T>,
+}
+impl<T: Intrusive> WeakAlias<T> {
+    /// Create a WeakAlias referring to the `T` field of `container`.
+    pub fn of_container(container: &T::Container) -> WeakAlias<T> {
+        debug_check_field_bounds::<T>();
+        WeakAlias {
+            pointer: field_alias::<T>(IntrusiveAlias::new_of(container)),
+            marker: marker::PhantomData,
+        }
+    }
+    /// Create a WeakAlias from the address of a `T` field.
+    pub fn from_alias(pointer: IntrusiveAlias) -> WeakAlias<T> {
+        WeakAlias {
+            pointer,
+            marker: marker::PhantomData,
+        }
+    }
+    /// Get the address of the referenced field.
+    pub fn as_alias(&self) -> &IntrusiveAlias {
+        &self.pointer
+    }
+    /// Borrow the referenced field, with a lifetime taken from
+    /// `_lifetime`.
+    /// # Safety
+    /// Nothing tracks whether the target of a WeakAlias is still
+    /// alive. The caller must ensure that the container is alive and
+    /// outlived by `_lifetime`, and that no mutable references are
+    /// made to it while the resulting BorrowBox is alive.
+    pub unsafe fn upgrade<'a, U: ?Sized>(&self, _lifetime: &'a U) -> BorrowBox<'a, T> {
+        BorrowBox::new_from(self.pointer, _lifetime)
+    }
+}
+impl<T> Clone for WeakAlias<T> {
+    fn clone(&self) -> WeakAlias<T> {
+        *self
+    }
+}
+impl<T> Copy for WeakAlias<T> {}
+impl<T> cmp::PartialEq for WeakAlias<T> {
+    fn eq(&self, other: &WeakAlias<T>) -> bool {
+        self.pointer == other.pointer
+    }
+}
+impl<T> cmp::Eq for WeakAlias<T> {}
+impl<T> fmt::Debug for WeakAlias<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("WeakAlias").field(&self.pointer).finish()
+    }
+}
+
+/// Types whose intrusive links can be reset to the unlinked state.
+/// Implemented here for the link field types the crate provides;
+/// containers implement it by resetting each of their link fields,
+/// and should call it before a freshly-allocated (or recycled) node
+/// is first linked, so that no stale link survives:
+///
+/// ```
+/// # extern crate containerof;
+/// # use containerof::*;
+/// # fn main() {
+/// struct Node {
+///     value: u32,
+///     next: Option<IntrusiveAlias>,
+/// }
+/// impl LinkInit for Node {
+///     fn init_links(&mut self) {
+///         self.next.init_links();
+///     }
+/// }
+/// # }
+/// ```
+pub trait LinkInit {
+    /// Reset every intrusive link to the unlinked state.
+    fn init_links(&mut self);
+}
+impl LinkInit for IntrusiveAlias {
+    fn init_links(&mut self) {
+        *self = IntrusiveAlias::null();
+    }
+}
+impl LinkInit for Option<IntrusiveAlias> {
+    fn init_links(&mut self) {
+        *self = None;
+    }
+}
+impl LinkInit for Option<NonNullIntrusiveAlias> {
+    fn init_links(&mut self) {
+        *self = None;
+    }
+}
+
+/// Wrapper around an intrusive pointer that dereferences to its
+/// container, for code that mostly works with the container rather
+/// than the intrusive field. (A blanket `Deref` on intrusive types
+/// themselves would make it ambiguous which of the two is meant.)
+pub struct ContainerView<T: Intrusive>(pub T);
+impl<T: Intrusive> ContainerView<T> {
+    /// Unwrap the intrusive pointer.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+impl<T: Intrusive> ops::Deref for ContainerView<T> {
+    type Target = T::Container;
+    fn deref(&self) -> &T::Container {
+        self.0.as_container()
+    }
+}
+impl<T: Intrusive> ops::DerefMut for ContainerView<T> {
+    fn deref_mut(&mut self) -> &mut T::Container {
+        self.0.as_container_mut()
+    }
+}
 
 /// Minimal trait that, when implemented for a type, allows for the
 /// blanket implementation of the `Intrusive`` trait for that type. This
@@ -317,6 +1292,10 @@ pub trait IntrusiveBase: Sized {
     /// Allow using type-safe intrusive pointer as generic intrusive
     /// pointer.
     fn as_alias(&self) -> &IntrusiveAlias;
+
+    /// Allow using type-safe intrusive pointer as mutable generic
+    /// intrusive pointer.
+    fn as_alias_mut(&mut self) -> &mut IntrusiveAlias;
 }
 
 /// Trait defining routines for translation between containing
@@ -326,22 +1305,31 @@ pub trait IntrusiveBase: Sized {
 pub trait Intrusive: IntrusiveBase {
     /// Ownership-moving translation from generic intrusive pointer
     /// alias to type-safe intrusive pointer.
+    ///
+    /// This is the consuming counterpart to `of_alias`: `ia` is taken
+    /// by value, and ownership of the container it refers to moves
+    /// into the result. `into_alias` moves ownership back out again.
     /// # Safety
     /// This converts a raw pointer to an owned pointer, which is unsafe
-    /// per Rust's memory model.
+    /// per Rust's memory model. The caller must not use `ia`, or any
+    /// copy of it, while the result is alive. Debug builds panic if
+    /// the container address implied by `ia` is not aligned for the
+    /// container type.
     unsafe fn from_alias(ia: IntrusiveAlias) -> Self;
 
+    /// As `from_alias`, but returning `None` if `ia` is null.
+    /// # Safety
+    /// Apart from the null check, the caller must uphold the same
+    /// contract as for `from_alias`.
+    unsafe fn try_from_alias(ia: IntrusiveAlias) -> Option<Self>;
+
     /// Ownership-moving translation from type-safe intrusive pointer
-    /// to generic intrusive pointer.
+    /// to generic intrusive pointer. (Inverse of `from_alias`.)
     /// # Safety
     /// This converts an owned pointer to a raw pointer, which is unsafe
     /// per Rust's memory model.
     unsafe fn into_alias(self) -> IntrusiveAlias;
 
-    /// Allow using type-safe intrusive pointer as mutable generic
-    /// intrusive pointer.
-    fn as_alias_mut(&mut self) -> &mut IntrusiveAlias;
-
     /// Allow using generic intrusive pointer as type-safe intrusive
     /// pointer.
     /// # Safety
@@ -356,6 +1344,26 @@ pub trait Intrusive: IntrusiveBase {
     /// which is unsafe per Rust's memory model.
     unsafe fn of_alias_mut(ia: &mut IntrusiveAlias) -> &mut Self;
 
+    /// Allow using generic intrusive pointer as type-safe intrusive
+    /// pointer, with a lifetime taken from `_lifetime` rather than
+    /// from a borrow of the alias. This is useful when the alias is
+    /// stored in another node's link, and the result should live as
+    /// long as the structure holding that node (as when iterating).
+    /// # Safety
+    /// The caller must ensure that `ia` points to a valid alias, that
+    /// both the alias and the container it refers to outlive
+    /// `_lifetime`, and that no conflicting mutable references are
+    /// made to either while the result is alive.
+    unsafe fn of_alias_lt<U: ?Sized>(ia: *const IntrusiveAlias, _lifetime: &U) -> &Self;
+
+    /// Get the memory layout of the container type, e.g. for sizing
+    /// the slots of a pool of containers.
+    fn container_layout() -> Layout;
+
+    /// Get the memory layout of the intrusive field type. (The field
+    /// lies `offset()` bytes into the container.)
+    fn field_layout() -> Layout;
+
     /// Represent ownership of a container as ownership of an Intrusive
     /// pointer type. (Inverse of `into_container`.)
     fn from_container(c: OwnBox<Self::Container>) -> Self;
@@ -364,14 +1372,50 @@ pub trait Intrusive: IntrusiveBase {
     /// its container. (Inverse of `from_container`.)
     fn into_container(self) -> OwnBox<Self::Container>;
 
+    /// Represent ownership of a container, held as a raw pointer, as
+    /// ownership of an Intrusive pointer type. This is the raw-pointer
+    /// analog of `from_container`. (Inverse of `into_container_ptr`.)
+    /// # Safety
+    /// The caller must ensure that `c` points to a valid container,
+    /// and that ownership of the container is transferred to the
+    /// result: `c` must not be used again while the result is alive.
+    unsafe fn from_container_ptr(c: *mut Self::Container) -> Self;
+
+    /// Release ownership of an Intrusive pointer type as a raw pointer
+    /// to its container. This is the raw-pointer analog of
+    /// `into_container`; the caller becomes responsible for the
+    /// container. (Inverse of `from_container_ptr`.)
+    fn into_container_ptr(self) -> *mut Self::Container;
+
+    /// Represent ownership of an Intrusive pointer type as ownership of
+    /// its container, once the container has been removed from every
+    /// structure it was linked into. Equivalent to `into_container`,
+    /// except that debug builds check `is_detached` against the
+    /// container first, and panic if it returns `false`, catching a
+    /// node that is released while still linked.
+    fn detach<F>(self, is_detached: F) -> OwnBox<Self::Container>
+    where
+        F: FnOnce(&Self::Container) -> bool;
+
     /// Represent a borrow of an intrusive type via a borrow of its
     /// container.
-    fn of_container(c: &Self::Container) -> BorrowBox<Self>;
+    fn of_container(c: &Self::Container) -> BorrowBox<'_, Self>;
 
     /// Represent a mutable borrow of an intrusive type via a mutable
     /// borrow of its container.
     fn of_container_mut(c: &mut Self::Container) -> BorrowBoxMut<'_, Self>;
 
+    /// Represent a mutable borrow of an intrusive type via a pinned
+    /// mutable borrow of its container, for containers (such as
+    /// futures with embedded waiter links) that must not move once
+    /// linked.
+    /// # Safety
+    /// The returned borrow grants `&mut` access to the container
+    /// (e.g. via `as_container_mut`). The caller must uphold the
+    /// pinning guarantee: the container must not be moved out of (as
+    /// with `mem::swap` or `mem::replace`) through the borrow.
+    unsafe fn of_container_pinned(c: Pin<&mut Self::Container>) -> Pin<BorrowBoxMut<'_, Self>>;
+
     /// Grant referential access to the container of this intrusive
     /// pointer type.
     fn as_container(&self) -> &Self::Container;
@@ -379,17 +1423,66 @@ pub trait Intrusive: IntrusiveBase {
     /// intrusive pointer type.
     fn as_container_mut(&mut self) -> &mut Self::Container;
 
+    /// Replace the container's value with `new`, returning the old
+    /// value, while the container itself stays where it is (and so
+    /// stays reachable through any links pointing into it).
+    ///
+    /// The whole container is overwritten, including this intrusive
+    /// field and any other links it holds. The caller must carry the
+    /// link state over into `new` (e.g. by copying the old links into
+    /// it first), or the structures the container belongs to will be
+    /// corrupted.
+    fn replace_container(&mut self, new: Self::Container) -> Self::Container;
+
+    /// Get a raw pointer to the container of this intrusive pointer
+    /// type. Unlike `as_container`, this does not form a reference,
+    /// so it makes no claim that the container is initialized or free
+    /// of conflicting borrows; the caller decides when (and whether)
+    /// it is valid to dereference.
+    fn container_ptr(&self) -> *const Self::Container;
+    /// Get a raw mutable pointer to the container of this intrusive
+    /// pointer type. (See `container_ptr`.)
+    fn container_ptr_mut(&mut self) -> *mut Self::Container;
+
+    /// Represent a borrow of this intrusive pointer as a borrow of a
+    /// sibling intrusive field `U` within the same container, without
+    /// forming an intermediate reference to the container.
+    fn project<U>(&self) -> BorrowBox<'_, U>
+    where
+        U: Intrusive<Container = Self::Container>;
+
+    /// Returns `true` if this intrusive pointer and `other` (which
+    /// may be a different intrusive field of the same container type)
+    /// refer to the same container. This compares container
+    /// addresses, i.e. identity, not container values. (`BorrowBox`
+    /// and `BorrowBoxMut` get this through `Deref`.)
+    fn same_container<U>(&self, other: &U) -> bool
+    where
+        U: Intrusive<Container = Self::Container>;
+
+    /// Compare the containers of this intrusive pointer and `other`
+    /// by the key that `key` extracts from each.
+    fn cmp_by<K, F>(&self, other: &Self, key: F) -> cmp::Ordering
+    where
+        K: Ord,
+        F: Fn(&Self::Container) -> &K;
+
     /// Assuming the "field" is a field in the container object, take
     /// ownership of the field as an intrusive pointer, allowing
     /// eventual translation back to the container. (Inverse of
     /// `into_field`.)
     /// # Safety
     /// The caller must ensure that the `OwnBox` argument was
-    /// constructed from this type.
+    /// constructed from this type. Unless `Self::Container` is
+    /// `Send`, the `OwnBox` must also not have been sent to another
+    /// thread since `into_field` returned it.
     unsafe fn from_field(c: OwnBox<Self::Field>) -> Self;
 
     /// Represent ownership of the container object as ownership of
     /// the intrusive field in the object. (Inverse of `from_field`.)
+    /// The result is `Send` whenever `Self::Field` is, even if the
+    /// container is not, so it must stay on this thread until passed
+    /// to `from_field` unless `Self::Container` is `Send`.
     fn into_field(self) -> OwnBox<Self::Field>;
 
     /// Represent a borrow of an intrusive type via a borrow of the
@@ -399,6 +1492,20 @@ pub trait Intrusive: IntrusiveBase {
     /// constructed from this type.
     unsafe fn of_field(c: &Self::Field) -> BorrowBox<'_, Self>;
 
+    /// Represent a borrow of an intrusive type via a pointer to the
+    /// intrusive field, with a lifetime taken from `_lifetime` rather
+    /// than from a borrow of the field. (The field analog of
+    /// `of_alias_lt`.)
+    /// # Safety
+    /// The caller must ensure that `field` points to a field
+    /// constructed from this type, that its container outlives
+    /// `_lifetime`, and that no conflicting mutable references are
+    /// made to the container while the result is alive.
+    unsafe fn of_field_lt<U: ?Sized>(
+        field: *const Self::Field,
+        _lifetime: &U,
+    ) -> BorrowBox<'_, Self>;
+
     /// Represent a mutable borrow of an intrusive type via a mutable
     /// borrow of the intrusive field.
     /// # Safety
@@ -413,21 +1520,92 @@ pub trait Intrusive: IntrusiveBase {
     /// Grant mutable referential access to the intrusive field represented by
     /// this intrusive pointer.
     fn as_field_mut(&mut self) -> &mut Self::Field;
+
+    /// Get a raw pointer to the intrusive field represented by this
+    /// intrusive pointer, without forming a reference. (The field
+    /// analog of `container_ptr`.)
+    fn as_field_ptr(&self) -> *const Self::Field;
+    /// Get a raw mutable pointer to the intrusive field represented
+    /// by this intrusive pointer. (See `as_field_ptr`.)
+    fn as_field_ptr_mut(&mut self) -> *mut Self::Field;
+}
+
+/// Check (in debug builds) that `T`'s field lies within its
+/// container, which catches a macro invocation or hand-written
+/// `IntrusiveBase` impl that names the wrong field type.
+#[inline]
+fn debug_check_field_bounds<T: IntrusiveBase>() {
+    debug_assert!(
+        <T as IntrusiveBase>::offset() + mem::size_of::<T::Field>()
+            <= mem::size_of::<T::Container>(),
+        "intrusive field (offset {}, size {}) extends past the end of its container (size {})",
+        <T as IntrusiveBase>::offset(),
+        mem::size_of::<T::Field>(),
+        mem::size_of::<T::Container>()
+    );
+}
+
+/// Check (in debug builds) that `field` is suitably aligned to be the
+/// `T` field of a container, which catches a declared `Field` type
+/// that does not match the actual field.
+#[inline]
+fn debug_check_field_alignment<T: IntrusiveBase>(field: IntrusiveAlias) {
+    debug_assert!(
+        field.addr() & (mem::align_of::<T::Field>() - 1) == 0,
+        "intrusive field address {:p} is not aligned for the field type",
+        field.get_address()
+    );
+    debug_assert!(
+        container_alias::<T>(field).addr() & (mem::align_of::<T::Container>() - 1) == 0,
+        "intrusive field address {:p} does not lie in an aligned container",
+        field.get_address()
+    );
+}
+
+/// Translate the address of a container into the address of its `T`
+/// field. A field at offset zero is the common case (e.g. a link as
+/// the first member), and is spelled out so that the container
+/// pointer is then reused as-is.
+#[inline]
+fn field_alias<T: IntrusiveBase>(container: IntrusiveAlias) -> IntrusiveAlias {
+    if <T as IntrusiveBase>::offset() == 0 {
+        container
+    } else {
+        container.byte_add(<T as IntrusiveBase>::offset())
+    }
+}
+/// Translate the address of a `T` field into the address of its
+/// container. (Inverse of `field_alias`.)
+#[inline]
+fn container_alias<T: IntrusiveBase>(field: IntrusiveAlias) -> IntrusiveAlias {
+    if <T as IntrusiveBase>::offset() == 0 {
+        field
+    } else {
+        field.byte_sub(<T as IntrusiveBase>::offset())
+    }
 }
 
 impl<T: IntrusiveBase> Intrusive for T {
     #[inline]
     unsafe fn from_alias(ia: IntrusiveAlias) -> T {
+        debug_assert!(
+            container_alias::<T>(ia).addr() & (mem::align_of::<T::Container>() - 1) == 0,
+            "Intrusive::from_alias: address {:p} is not aligned for the container type",
+            ia.get_address()
+        );
         <T as IntrusiveBase>::new(ia)
     }
     #[inline]
-    unsafe fn into_alias(self) -> IntrusiveAlias {
-        *self.as_alias()
+    unsafe fn try_from_alias(ia: IntrusiveAlias) -> Option<T> {
+        if ia.is_null() {
+            None
+        } else {
+            Some(<T as Intrusive>::from_alias(ia))
+        }
     }
-    #[allow(mutable_transmutes)]
     #[inline]
-    fn as_alias_mut(&mut self) -> &mut IntrusiveAlias {
-        unsafe { mem::transmute(self.as_alias()) }
+    unsafe fn into_alias(self) -> IntrusiveAlias {
+        *self.as_alias()
     }
     #[inline]
     unsafe fn of_alias(ia: &IntrusiveAlias) -> &T {
@@ -435,65 +1613,130 @@ impl<T: IntrusiveBase> Intrusive for T {
     }
     #[inline]
     unsafe fn of_alias_mut(ia: &mut IntrusiveAlias) -> &mut T {
-        &mut *(ia as *const _ as *mut T)
+        &mut *(ia as *mut IntrusiveAlias as *mut T)
+    }
+    #[inline]
+    unsafe fn of_alias_lt<U: ?Sized>(ia: *const IntrusiveAlias, _lifetime: &U) -> &T {
+        &*(ia as *const T)
+    }
+    #[inline]
+    fn container_layout() -> Layout {
+        Layout::new::<T::Container>()
+    }
+    #[inline]
+    fn field_layout() -> Layout {
+        Layout::new::<T::Field>()
     }
     #[inline]
     fn from_container(c: OwnBox<T::Container>) -> Self {
-        unsafe {
-            let addr: *const u8 = c
-                .as_alias()
-                .get_address()
-                .cast::<u8>()
-                .add(<T as IntrusiveBase>::offset());
-            mem::forget(c);
-            <T as Intrusive>::from_alias(IntrusiveAlias(addr.cast()))
-        }
+        debug_check_field_bounds::<T>();
+        let fieldptr = field_alias::<T>(c.into_alias());
+        unsafe { <T as Intrusive>::from_alias(fieldptr) }
     }
     #[inline]
     fn into_container(self) -> OwnBox<T::Container> {
-        unsafe {
-            let fieldptr = self.as_alias().get_address().cast::<u8>();
-            let containerptr = fieldptr.sub(<T as IntrusiveBase>::offset());
-            OwnBox::from_alias(IntrusiveAlias::new(containerptr.cast()))
-        }
+        debug_check_field_bounds::<T>();
+        let containerptr = container_alias::<T>(*self.as_alias());
+        unsafe { OwnBox::from_alias(containerptr) }
+    }
+    #[inline]
+    unsafe fn from_container_ptr(c: *mut T::Container) -> Self {
+        debug_check_field_bounds::<T>();
+        let fieldptr = field_alias::<T>(IntrusiveAlias::new(c as *const ()));
+        <T as Intrusive>::from_alias(fieldptr)
+    }
+    #[inline]
+    fn into_container_ptr(self) -> *mut T::Container {
+        debug_check_field_bounds::<T>();
+        let containerptr = container_alias::<T>(*self.as_alias());
+        containerptr.get_address() as *mut T::Container
+    }
+    #[inline]
+    fn detach<F>(self, is_detached: F) -> OwnBox<T::Container>
+    where
+        F: FnOnce(&T::Container) -> bool,
+    {
+        debug_assert!(
+            is_detached(self.as_container()),
+            "Intrusive::detach: container is still linked"
+        );
+        self.into_container()
     }
     #[inline]
     fn of_container(container: &T::Container) -> BorrowBox<'_, T> {
-        let addr = (container as *const T::Container).cast::<u8>();
-        unsafe {
-            let fieldptr = addr.add(<T as IntrusiveBase>::offset());
-            BorrowBox::new_from(IntrusiveAlias::new(fieldptr.cast()), container)
-        }
+        debug_check_field_bounds::<T>();
+        let fieldptr = field_alias::<T>(IntrusiveAlias::new_of(container));
+        unsafe { BorrowBox::new_from(fieldptr, container) }
     }
     #[inline]
     fn of_container_mut(container: &mut T::Container) -> BorrowBoxMut<'_, T> {
-        let addr = (container as *mut T::Container).cast::<u8>();
-        unsafe {
-            let fieldptr = addr.add(<T as IntrusiveBase>::offset());
-            BorrowBoxMut::new_from(IntrusiveAlias::new(fieldptr.cast()), container)
-        }
+        debug_check_field_bounds::<T>();
+        let addr = IntrusiveAlias::new((container as *mut T::Container).cast());
+        let fieldptr = field_alias::<T>(addr);
+        unsafe { BorrowBoxMut::new_from(fieldptr, container) }
+    }
+    #[inline]
+    unsafe fn of_container_pinned(container: Pin<&mut T::Container>) -> Pin<BorrowBoxMut<'_, T>> {
+        Pin::new_unchecked(<T as Intrusive>::of_container_mut(
+            container.get_unchecked_mut(),
+        ))
     }
     #[inline]
     fn as_container(&self) -> &T::Container {
-        unsafe {
-            let fieldptr = self.as_alias().get_address().cast::<u8>();
-            let containerptr = fieldptr.sub(<T as IntrusiveBase>::offset());
-            &*(containerptr as *const T::Container)
-        }
+        unsafe { &*self.container_ptr() }
     }
-    #[allow(mutable_transmutes)]
     #[inline]
     fn as_container_mut(&mut self) -> &mut T::Container {
-        unsafe { mem::transmute(self.as_container()) }
+        unsafe { &mut *self.container_ptr_mut() }
+    }
+    #[inline]
+    fn replace_container(&mut self, new: T::Container) -> T::Container {
+        mem::replace(self.as_container_mut(), new)
+    }
+    #[inline]
+    fn container_ptr(&self) -> *const T::Container {
+        let containerptr = container_alias::<T>(*self.as_alias());
+        containerptr.get_address() as *const T::Container
+    }
+    #[inline]
+    fn container_ptr_mut(&mut self) -> *mut T::Container {
+        let containerptr = container_alias::<T>(*self.as_alias());
+        containerptr.get_address() as *mut T::Container
+    }
+    #[inline]
+    fn project<U>(&self) -> BorrowBox<'_, U>
+    where
+        U: Intrusive<Container = T::Container>,
+    {
+        let containerptr = container_alias::<T>(*self.as_alias());
+        let fieldptr = field_alias::<U>(containerptr);
+        unsafe { BorrowBox::new_from(fieldptr, self) }
+    }
+    #[inline]
+    fn same_container<U>(&self, other: &U) -> bool
+    where
+        U: Intrusive<Container = T::Container>,
+    {
+        self.container_ptr() == other.container_ptr()
+    }
+    #[inline]
+    fn cmp_by<K, F>(&self, other: &T, key: F) -> cmp::Ordering
+    where
+        K: Ord,
+        F: Fn(&T::Container) -> &K,
+    {
+        key(self.as_container()).cmp(key(other.as_container()))
     }
     #[inline]
     unsafe fn from_field(c: OwnBox<T::Field>) -> T {
+        debug_check_field_alignment::<T>(*c.as_alias());
         let addr = c.as_alias().get_address();
         mem::forget(c);
         <T as Intrusive>::from_alias(IntrusiveAlias::new(addr))
     }
     #[inline]
     fn into_field(self) -> OwnBox<T::Field> {
+        debug_check_field_alignment::<T>(*self.as_alias());
         unsafe { OwnBox::from_alias(IntrusiveAlias(self.as_alias().get_address())) }
     }
     #[inline]
@@ -501,8 +1744,13 @@ impl<T: IntrusiveBase> Intrusive for T {
         BorrowBox::new_from(IntrusiveAlias::new_of(field), field)
     }
     #[inline]
+    unsafe fn of_field_lt<U: ?Sized>(field: *const T::Field, _lifetime: &U) -> BorrowBox<'_, T> {
+        BorrowBox::new_from(IntrusiveAlias::new(field as *const ()), _lifetime)
+    }
+    #[inline]
     unsafe fn of_field_mut(field: &mut T::Field) -> BorrowBoxMut<'_, T> {
-        BorrowBoxMut::new_from(IntrusiveAlias::new_of(field), field)
+        let fieldptr = IntrusiveAlias::new((field as *mut T::Field).cast());
+        BorrowBoxMut::new_from(fieldptr, field)
     }
     #[inline]
     fn as_field(&self) -> &T::Field {
@@ -512,4 +1760,12 @@ impl<T: IntrusiveBase> Intrusive for T {
     fn as_field_mut(&mut self) -> &mut T::Field {
         unsafe { &mut *(self.as_alias().get_address() as *mut _) }
     }
+    #[inline]
+    fn as_field_ptr(&self) -> *const T::Field {
+        self.as_alias().cast()
+    }
+    #[inline]
+    fn as_field_ptr_mut(&mut self) -> *mut T::Field {
+        self.as_alias().cast_mut()
+    }
 }
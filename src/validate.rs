@@ -0,0 +1,36 @@
+//! Structural checks for hand-built intrusive structures, for use in
+//! assertions and debugging.
+
+use Intrusive;
+use IntrusiveAlias;
+
+/// Returns `true` if following `next` links from `start` ever revisits
+/// a node, using Floyd's tortoise-and-hare algorithm (constant space,
+/// linear time). `start` is an alias to the `T` field of the first
+/// node, and `next` extracts the alias to the following node's `T`
+/// field from a container, or `None` at the end of the chain.
+/// # Safety
+/// Every alias reachable from `start` must refer to a live `T` field,
+/// and the chain must not be mutated during the walk.
+pub unsafe fn detect_cycle<T, F>(start: &IntrusiveAlias, next: F) -> bool
+where
+    T: Intrusive,
+    F: Fn(&T::Container) -> Option<&IntrusiveAlias>,
+{
+    let step = |alias: IntrusiveAlias| next(T::of_alias(&alias).as_container()).cloned();
+    let mut slow = *start;
+    let mut fast = *start;
+    loop {
+        fast = match step(fast).and_then(step) {
+            Some(alias) => alias,
+            None => return false,
+        };
+        slow = match step(slow) {
+            Some(alias) => alias,
+            None => return false,
+        };
+        if slow == fast {
+            return true;
+        }
+    }
+}
@@ -0,0 +1,63 @@
+#![cfg(all(test, feature = "allocator_api"))]
+#![feature(allocator_api)]
+extern crate containerof;
+use containerof::*;
+use std::alloc::{AllocError, Allocator, Layout};
+use std::cell::{Cell, UnsafeCell};
+use std::ptr::NonNull;
+
+struct BumpAllocator {
+    buffer: UnsafeCell<[u64; 16]>,
+    next: Cell<usize>,
+    freed: Cell<usize>,
+}
+impl BumpAllocator {
+    fn new() -> BumpAllocator {
+        BumpAllocator {
+            buffer: UnsafeCell::new([0; 16]),
+            next: Cell::new(0),
+            freed: Cell::new(0),
+        }
+    }
+    fn owns(&self, ptr: NonNull<u8>) -> bool {
+        let start = self.buffer.get() as usize;
+        let end = start + std::mem::size_of::<[u64; 16]>();
+        (start..end).contains(&(ptr.as_ptr() as usize))
+    }
+}
+unsafe impl Allocator for &BumpAllocator {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        assert!(layout.align() <= std::mem::align_of::<u64>());
+        let words = layout.size().div_ceil(std::mem::size_of::<u64>());
+        let start = self.next.get();
+        if start + words > 16 {
+            return Err(AllocError);
+        }
+        self.next.set(start + words);
+        let ptr = unsafe { (self.buffer.get() as *mut u64).add(start) as *mut u8 };
+        let slice = std::ptr::slice_from_raw_parts_mut(ptr, layout.size());
+        NonNull::new(slice).ok_or(AllocError)
+    }
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, _layout: Layout) {
+        assert!(self.owns(ptr));
+        self.freed.set(self.freed.get() + 1);
+    }
+}
+
+#[test]
+fn test_own_box_in_roundtrip() {
+    let bump = BumpAllocator::new();
+    let b = Box::new_in(42u64, &bump);
+    let b_addr = &*b as *const u64 as *const ();
+
+    let (ob, alloc) = OwnBox::from_box_in(b);
+    assert_eq!(b_addr, ob.get_address());
+    assert_eq!(42, *ob);
+    assert!(bump.owns(NonNull::new(ob.get_address() as *mut u8).unwrap()));
+
+    let b = unsafe { ob.into_box_in(alloc) };
+    assert_eq!(42, *b);
+    assert_eq!(0, bump.freed.get());
+    drop(b);
+    assert_eq!(1, bump.freed.get());
+}
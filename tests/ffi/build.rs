@@ -0,0 +1,10 @@
+extern crate cc;
+
+fn main() {
+    println!("cargo:rerun-if-changed=../../include/containerof.h");
+    println!("cargo:rerun-if-changed=shim.c");
+    cc::Build::new()
+        .file("shim.c")
+        .include("../../include")
+        .compile("containerof_ffi_shim");
+}
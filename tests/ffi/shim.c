@@ -0,0 +1,23 @@
+/* C shim for tests/ffi/src/lib.rs. The layout of `struct shim_node`
+ * must match the `#[repr(C)]` `Node` declared there. */
+#include "containerof.h"
+
+struct shim_link {
+    const void *next;
+};
+
+struct shim_node {
+    int value;
+    struct shim_link link;
+};
+
+int containerof_shim_node_value(containerof_alias link)
+{
+    const struct shim_link *l = containerof_alias_to_ptr(link);
+    return containerof_container_of(l, const struct shim_node, link)->value;
+}
+
+containerof_alias containerof_shim_link_of(const struct shim_node *node)
+{
+    return containerof_alias_from_ptr(&node->link);
+}
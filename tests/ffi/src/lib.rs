@@ -0,0 +1,41 @@
+//! Tests for the `ffi` feature of `containerof`, calling into the C
+//! shim in `shim.c` (compiled by this crate's build script). Run with
+//! `cargo test --manifest-path tests/ffi/Cargo.toml`.
+#![cfg(test)]
+extern crate containerof;
+use containerof::*;
+use std::os::raw::c_int;
+
+#[repr(C)]
+struct Link {
+    next: *const (),
+}
+
+#[repr(C)]
+struct Node {
+    value: c_int,
+    link: Link,
+}
+containerof_intrusive!(NodeLink = Node:link::Link);
+
+extern "C" {
+    fn containerof_shim_node_value(link: IntrusiveAlias) -> c_int;
+    fn containerof_shim_link_of(node: *const Node) -> IntrusiveAlias;
+}
+
+#[test]
+fn test_ffi_roundtrip() {
+    let node = Node {
+        value: 42,
+        link: Link {
+            next: std::ptr::null(),
+        },
+    };
+    assert!(node.link.next.is_null());
+    let link = <NodeLink as Intrusive>::of_container(&node);
+
+    // the C side finds the container from the alias...
+    assert_eq!(42, unsafe { containerof_shim_node_value(*link.as_alias()) });
+    // ...and builds the same alias from the container.
+    assert_eq!(*link.as_alias(), unsafe { containerof_shim_link_of(&node) });
+}
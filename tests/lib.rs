@@ -1,9 +1,11 @@
 #![cfg(test)]
 extern crate containerof;
 use containerof::*;
+use std::collections::hash_map::DefaultHasher;
 use std::convert;
+use std::hash::{Hash, Hasher};
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 struct MyStruct {
     field1: i32,
     field2: i32,
@@ -11,7 +13,7 @@ struct MyStruct {
 }
 
 #[derive(Debug)]
-#[allow(non_camel_case_types)]
+#[allow(non_camel_case_types, dead_code)]
 struct MyStructField2_Meth1(*const ());
 impl IntrusiveBase for MyStructField2_Meth1 {
     type Container = MyStruct;
@@ -23,12 +25,38 @@ impl IntrusiveBase for MyStructField2_Meth1 {
     unsafe fn new(ia: IntrusiveAlias) -> Self {
         MyStructField2_Meth1(ia.get_address())
     }
-    fn as_alias<'a>(&'a self) -> &'a IntrusiveAlias {
-        unsafe { ::std::mem::transmute(self as *const _) }
+    fn as_alias(&self) -> &IntrusiveAlias {
+        unsafe { &*(self as *const _ as *const IntrusiveAlias) }
+    }
+    fn as_alias_mut(&mut self) -> &mut IntrusiveAlias {
+        unsafe { &mut *(self as *mut _ as *mut IntrusiveAlias) }
     }
 }
 //containerof_intrusive!(MyStructField2_Meth2 = MyStruct:field2::i32);
 
+// Deliberately wrong: `field3` is an `i32`, so a `u64` at its offset
+// runs past the end of `MyStruct`.
+#[derive(Debug)]
+#[allow(non_camel_case_types, dead_code)]
+struct MyStructField3_Bogus(*const ());
+impl IntrusiveBase for MyStructField3_Bogus {
+    type Container = MyStruct;
+    type Field = u64;
+
+    fn offset() -> usize {
+        containerof_field_offset!(MyStruct:field3)
+    }
+    unsafe fn new(ia: IntrusiveAlias) -> Self {
+        MyStructField3_Bogus(ia.get_address())
+    }
+    fn as_alias(&self) -> &IntrusiveAlias {
+        unsafe { &*(self as *const _ as *const IntrusiveAlias) }
+    }
+    fn as_alias_mut(&mut self) -> &mut IntrusiveAlias {
+        unsafe { &mut *(self as *mut _ as *mut IntrusiveAlias) }
+    }
+}
+
 #[test]
 fn test_field_offset() {
     let ms = MyStruct {
@@ -64,7 +92,7 @@ fn test_intrusive_container_roundtrip() {
     let mc1_addr = mc1.get_address();
 
     let mcfield: MyStructField2_Meth1 = Intrusive::from_container(mc1);
-    let mcfieldcontainer: *const () = unsafe { ::std::mem::transmute(mcfield.as_container()) };
+    let mcfieldcontainer = mcfield.as_container() as *const MyStruct as *const ();
 
     assert_eq!(mc1_addr, mcfieldcontainer);
 
@@ -90,7 +118,7 @@ fn test_intrusive_field_roundtrip() {
     assert_eq!(mc1_field_addr, mcfield_as_addr);
 
     let mcfield = mcfield.into_field();
-    let mcfield_addr = unsafe { ::std::mem::transmute(&*mcfield) };
+    let mcfield_addr = &*mcfield as *const i32 as usize;
     assert_eq!(mc1_field_addr, mcfield_addr);
 
     let mcfield: MyStructField2_Meth1 = unsafe { Intrusive::from_field(mcfield) };
@@ -101,11 +129,10 @@ fn test_intrusive_field_roundtrip() {
     let _ = unsafe { mc2.into_box() };
 }
 
-// FIXME: how can we test that compilations fail with Cargo?
 // it's part of the contract of borrow_box() that certain
-// orders-of-operation are illegal, I'd like to have explicit
-// tests that I can leave in place to enforce that compilation
-// fails under those circumstances.
+// orders-of-operation are illegal. those are enforced by the
+// `compile_fail` doc-tests on `BorrowBox`, `BorrowBoxMut`, and
+// `OwnBox::into_alias`.
 #[test]
 fn test_borrow_box() {
     let mut mc = MyStruct {
@@ -125,3 +152,1217 @@ fn test_borrow_box() {
     }
     assert_eq!(10, mc.field2);
 }
+
+#[test]
+fn test_as_container_mut() {
+    let mc: OwnBox<_> = convert::From::from(Box::new(MyStruct {
+        field1: 1,
+        field2: 2,
+        field3: 3,
+    }));
+    let mut mcfield: MyStructField2_Meth1 = Intrusive::from_container(mc);
+    mcfield.as_container_mut().field1 = 10;
+    mcfield.as_container_mut().field3 = 30;
+    assert_eq!(10, mcfield.as_container().field1);
+    assert_eq!(2, *mcfield.as_field());
+    assert_eq!(30, mcfield.as_container().field3);
+
+    let _ = unsafe { mcfield.into_container().into_box() };
+}
+
+#[test]
+fn test_as_alias_mut() {
+    let mc1: OwnBox<_> = convert::From::from(Box::new(MyStruct {
+        field1: 1,
+        field2: 2,
+        field3: 3,
+    }));
+    let mc2: OwnBox<_> = convert::From::from(Box::new(MyStruct {
+        field1: 4,
+        field2: 5,
+        field3: 6,
+    }));
+    let mut mcfield1: MyStructField2_Meth1 = Intrusive::from_container(mc1);
+    let mcfield2: MyStructField2_Meth1 = Intrusive::from_container(mc2);
+
+    // retarget the first intrusive pointer at the second container.
+    let original = *mcfield1.as_alias();
+    *mcfield1.as_alias_mut() = *mcfield2.as_alias();
+    assert_eq!(5, *mcfield1.as_field());
+    assert_eq!(4, mcfield1.as_container().field1);
+
+    *mcfield1.as_alias_mut() = original;
+    assert_eq!(2, *mcfield1.as_field());
+
+    let _ = unsafe { mcfield1.into_container().into_box() };
+    let _ = unsafe { mcfield2.into_container().into_box() };
+}
+
+#[test]
+fn test_of_alias_mut() {
+    let mc: OwnBox<_> = convert::From::from(Box::new(MyStruct {
+        field1: 1,
+        field2: 2,
+        field3: 3,
+    }));
+    let mcfield: MyStructField2_Meth1 = Intrusive::from_container(mc);
+    let mut alias = unsafe { mcfield.into_alias() };
+    {
+        let mcfield = unsafe { <MyStructField2_Meth1 as Intrusive>::of_alias_mut(&mut alias) };
+        *mcfield.as_field_mut() = 20;
+        mcfield.as_container_mut().field3 = 30;
+    }
+    let mcfield: MyStructField2_Meth1 = unsafe { Intrusive::from_alias(alias) };
+    assert_eq!(20, *mcfield.as_field());
+    assert_eq!(30, mcfield.as_container().field3);
+
+    let _ = unsafe { mcfield.into_container().into_box() };
+}
+
+#[test]
+fn test_own_box_deref() {
+    let mut ob = OwnBox::from_box(Box::new(MyStruct {
+        field1: 1,
+        field2: 2,
+        field3: 3,
+    }));
+    let value: &MyStruct = &ob;
+    assert_eq!(2, value.field2);
+    ob.field2 = 20;
+    assert_eq!(20, ob.field2);
+    assert_eq!(ob.get_address(), ob.as_alias().get_address());
+
+    let b = unsafe { ob.into_box() };
+    assert_eq!(20, b.field2);
+}
+
+#[test]
+fn test_own_box_new() {
+    let ob = OwnBox::new(MyStruct {
+        field1: 1,
+        field2: 2,
+        field3: 3,
+    });
+    let ob_addr = ob.get_address();
+    let b = unsafe { ob.into_box() };
+    assert_eq!(ob_addr, &*b as *const MyStruct as *const ());
+    assert_eq!(1, b.field1);
+    assert_eq!(2, b.field2);
+    assert_eq!(3, b.field3);
+}
+
+#[test]
+fn test_debug_format() {
+    let ob = OwnBox::new(MyStruct {
+        field1: 1,
+        field2: 2,
+        field3: 3,
+    });
+    let formatted = format!("{:?}", ob);
+    assert!(formatted.contains(&format!("{:p}", ob.get_address())));
+    assert!(formatted.contains("field2: 2"));
+    let _ = unsafe { ob.into_box() };
+
+    let mut mc = MyStruct {
+        field1: 1,
+        field2: 2,
+        field3: 3,
+    };
+    let field_addr = &mc.field2 as *const i32;
+    {
+        let mcfield = <MyStructField2_Meth1 as Intrusive>::of_container(&mc);
+        let formatted = format!("{:?}", mcfield);
+        assert!(formatted.starts_with("BorrowBox "));
+        assert!(formatted.contains(&format!("{:p}", field_addr)));
+        assert!(formatted.contains("field3: 3"));
+    }
+    {
+        let mcfield = <MyStructField2_Meth1 as Intrusive>::of_container_mut(&mut mc);
+        let formatted = format!("{:?}", mcfield);
+        assert!(formatted.starts_with("BorrowBoxMut "));
+        assert!(formatted.contains(&format!("{:p}", field_addr)));
+    }
+}
+
+fn hash_of<T: Hash>(t: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    t.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn test_own_box_identity() {
+    let ob1 = OwnBox::new(1u32);
+    let ob2 = OwnBox::new(1u32);
+    let ob1_alias: OwnBox<u32> = unsafe { OwnBox::from_alias(*ob1.as_alias()) };
+
+    assert!(ob1 == ob1_alias);
+    assert_eq!(hash_of(&ob1), hash_of(&ob1_alias));
+    assert_eq!(::std::cmp::Ordering::Equal, ob1.cmp(&ob1_alias));
+
+    // equal values at different addresses are not equal.
+    assert!(ob1 != ob2);
+    assert_ne!(hash_of(&ob1), hash_of(&ob2));
+    assert_eq!(ob1.get_address().cmp(&ob2.get_address()), ob1.cmp(&ob2));
+
+    let _ = ob1_alias.into_alias();
+    let _ = unsafe { ob1.into_box() };
+    let _ = unsafe { ob2.into_box() };
+}
+
+#[test]
+fn test_own_box_as_ptr() {
+    let b = Box::new(MyStruct {
+        field1: 1,
+        field2: 2,
+        field3: 3,
+    });
+    let b_addr = &*b as *const MyStruct;
+    let mut ob = OwnBox::from_box(b);
+    assert_eq!(b_addr, ob.as_ptr());
+    assert_eq!(b_addr as *mut MyStruct, ob.as_mut_ptr());
+    unsafe { (*ob.as_mut_ptr()).field3 = 30 };
+    assert_eq!(30, unsafe { (*ob.as_ptr()).field3 });
+
+    let _ = unsafe { ob.into_box() };
+}
+
+fn assert_send<T: Send>() {}
+fn assert_sync<T: Sync>() {}
+
+#[test]
+fn test_send_sync() {
+    assert_send::<OwnBox<u64>>();
+    assert_sync::<OwnBox<u64>>();
+    assert_send::<BorrowBox<MyStructField2_Meth1>>();
+    assert_sync::<BorrowBox<MyStructField2_Meth1>>();
+    assert_send::<BorrowBoxMut<MyStructField2_Meth1>>();
+    assert_sync::<BorrowBoxMut<MyStructField2_Meth1>>();
+
+    let ob = OwnBox::new(42u64);
+    let ob = ::std::thread::spawn(move || {
+        assert_eq!(42, *ob);
+        ob
+    })
+    .join()
+    .unwrap();
+    let _ = unsafe { ob.into_box() };
+}
+
+#[test]
+fn test_own_box_leak() {
+    let ob = OwnBox::new(MyStruct {
+        field1: 1,
+        field2: 2,
+        field3: 3,
+    });
+    let ob_addr = ob.get_address();
+    let leaked: &'static mut MyStruct = ob.leak();
+    assert_eq!(ob_addr, leaked as *const MyStruct as *const ());
+    leaked.field2 = 20;
+    assert_eq!(20, leaked.field2);
+}
+
+#[test]
+#[cfg_attr(
+    any(debug_assertions, feature = "strict_linearity"),
+    should_panic(expected = "OwnBox must be consumed")
+)]
+fn test_own_box_drop() {
+    let b = Box::new(1u32);
+    let b_addr = &*b as *const u32 as *mut u32;
+    let ob = OwnBox::from_box(b);
+    // reclaim the allocation even when dropping `ob` panics.
+    struct FreeOnDrop(*mut u32);
+    impl Drop for FreeOnDrop {
+        fn drop(&mut self) {
+            let _ = unsafe { Box::from_raw(self.0) };
+        }
+    }
+    let _free = FreeOnDrop(b_addr);
+    drop(ob);
+}
+
+#[test]
+fn test_intrusive_alias_ordering() {
+    let values = [1u32, 2, 3];
+    let low = IntrusiveAlias::new_of(&values[0]);
+    let mid = IntrusiveAlias::new_of(&values[1]);
+    let high = IntrusiveAlias::new_of(&values[2]);
+
+    let mut aliases = vec![high, low, mid];
+    aliases.sort();
+    assert_eq!(vec![low, mid, high], aliases);
+    assert!(low < high);
+
+    assert_eq!(hash_of(&low), hash_of(&IntrusiveAlias::new_of(&values[0])));
+    assert_ne!(hash_of(&low), hash_of(&high));
+}
+
+#[test]
+fn test_intrusive_alias_null() {
+    let null = IntrusiveAlias::null();
+    assert!(null.is_null());
+    assert_eq!(None, IntrusiveAlias::new_checked(::std::ptr::null()));
+    assert!(unsafe { OwnBox::<u32>::try_from_alias(null) }.is_none());
+
+    let ob = OwnBox::new(7u32);
+    let alias = IntrusiveAlias::new_checked(ob.into_alias().get_address()).unwrap();
+    assert!(!alias.is_null());
+    let ob = unsafe { OwnBox::<u32>::try_from_alias(alias) }.unwrap();
+    assert_eq!(7, *ob);
+    let _ = unsafe { ob.into_box() };
+}
+
+#[test]
+fn test_intrusive_alias_arithmetic() {
+    let ms = MyStruct {
+        field1: 1,
+        field2: 2,
+        field3: 3,
+    };
+    let base = IntrusiveAlias::new_of(&ms);
+    let offset = containerof_field_offset!(MyStruct: field3);
+
+    assert_eq!(base, base.byte_add(offset).byte_sub(offset));
+    assert_eq!(
+        base,
+        base.offset_by(offset as isize)
+            .offset_by(-(offset as isize))
+    );
+    assert_eq!(IntrusiveAlias::new_of(&ms.field3), base.byte_add(offset));
+    assert_eq!(base.byte_add(offset), base.offset_by(offset as isize));
+    assert_eq!(
+        base.get_address() as usize + offset,
+        base.byte_add(offset).get_address() as usize
+    );
+    assert_eq!(
+        IntrusiveAlias::new_of(&ms.field1),
+        IntrusiveAlias::new_of(&ms.field3)
+            .byte_sub(offset - containerof_field_offset!(MyStruct: field1))
+    );
+}
+
+#[test]
+fn test_container_of() {
+    let mut ms = MyStruct {
+        field1: 1,
+        field2: 2,
+        field3: 3,
+    };
+    let ms_addr = &ms as *const MyStruct;
+    let field3_ptr = &ms.field3 as *const i32;
+    assert_eq!(ms_addr, unsafe {
+        container_of!(field3_ptr, MyStruct, field3)
+    });
+
+    let field2_ptr = &mut ms.field2 as *mut i32;
+    let ms_ptr = unsafe { container_of_mut!(field2_ptr, MyStruct, field2) };
+    assert_eq!(ms_addr as *mut MyStruct, ms_ptr);
+    unsafe { (*ms_ptr).field1 = 10 };
+    assert_eq!(10, ms.field1);
+}
+
+#[test]
+fn test_intrusive_alias_tag() {
+    let value = 5u64;
+    let alias = IntrusiveAlias::new_of(&value);
+    assert_eq!(0, alias.tag::<u64>());
+    for tag in 0..=3 {
+        let tagged = alias.with_tag::<u64>(tag);
+        assert_eq!(tag, tagged.tag::<u64>());
+        assert_eq!(alias, tagged.untagged::<u64>());
+        assert_eq!(5, unsafe {
+            *(tagged.untagged::<u64>().get_address() as *const u64)
+        });
+    }
+}
+
+#[test]
+#[should_panic(expected = "tag does not fit")]
+fn test_intrusive_alias_tag_too_large() {
+    let value = 5u64;
+    let _ = IntrusiveAlias::new_of(&value).with_tag::<u64>(::std::mem::align_of::<u64>());
+}
+
+struct Waiter {
+    woken: bool,
+    next: Option<IntrusiveAlias>,
+    _pinned: ::std::marker::PhantomPinned,
+}
+containerof_intrusive!(WaiterLink = Waiter:next::Option<IntrusiveAlias>);
+
+#[test]
+fn test_of_container_pinned() {
+    let mut waiter = Box::pin(Waiter {
+        woken: false,
+        next: None,
+        _pinned: ::std::marker::PhantomPinned,
+    });
+    let waiter_addr = &*waiter as *const Waiter;
+
+    // link the pinned waiter into a (singly-linked) wait list.
+    let mut head: Option<IntrusiveAlias> = {
+        let link = unsafe { <WaiterLink as Intrusive>::of_container_pinned(waiter.as_mut()) };
+        assert_eq!(waiter_addr, link.as_container() as *const Waiter);
+        Some(*link.as_alias())
+    };
+
+    // wake and unlink it through the list.
+    {
+        let mut link: WaiterLink = unsafe { Intrusive::from_alias(head.take().unwrap()) };
+        assert_eq!(waiter_addr, link.as_container() as *const Waiter);
+        link.as_container_mut().woken = true;
+        head = link.as_field_mut().take();
+    }
+    assert!(head.is_none());
+    assert!(waiter.woken);
+}
+
+#[test]
+fn test_atomic_intrusive_alias() {
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+
+    let values = [1u32, 2];
+    let first = IntrusiveAlias::new_of(&values[0]);
+    let second = IntrusiveAlias::new_of(&values[1]);
+
+    let mut atomic = AtomicIntrusiveAlias::null();
+    assert!(atomic.load(Ordering::Acquire).is_null());
+    atomic.store(first, Ordering::Release);
+    assert_eq!(first, atomic.load(Ordering::Acquire));
+    assert_eq!(
+        Err(first),
+        atomic.compare_exchange(second, second, Ordering::AcqRel, Ordering::Acquire)
+    );
+    assert_eq!(
+        Ok(first),
+        atomic.compare_exchange(first, second, Ordering::AcqRel, Ordering::Acquire)
+    );
+    assert_eq!(second, atomic.swap(first, Ordering::AcqRel));
+    *atomic.get_mut() = second;
+    assert_eq!(second, atomic.into_inner());
+
+    // concurrent compare-exchange loops never lose an update.
+    let counter = Arc::new(AtomicIntrusiveAlias::null());
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            let counter = counter.clone();
+            ::std::thread::spawn(move || {
+                for _ in 0..1000 {
+                    let mut current = counter.load(Ordering::Relaxed);
+                    while let Err(actual) = counter.compare_exchange_weak(
+                        current,
+                        current.byte_add(1),
+                        Ordering::AcqRel,
+                        Ordering::Relaxed,
+                    ) {
+                        current = actual;
+                    }
+                }
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert_eq!(4000, counter.load(Ordering::Acquire).get_address() as usize);
+}
+
+#[test]
+fn test_non_null_intrusive_alias() {
+    use std::mem::size_of;
+    assert_eq!(
+        size_of::<usize>(),
+        size_of::<Option<NonNullIntrusiveAlias>>()
+    );
+
+    let value = 3u32;
+    let alias = IntrusiveAlias::new_of(&value);
+    let non_null = NonNullIntrusiveAlias::new_of(&value);
+    assert_eq!(Some(non_null), NonNullIntrusiveAlias::from_alias(alias));
+    assert_eq!(
+        None,
+        NonNullIntrusiveAlias::from_alias(IntrusiveAlias::null())
+    );
+    assert_eq!(&alias, non_null.as_alias());
+    assert_eq!(alias, IntrusiveAlias::from(non_null));
+    assert_eq!(alias.get_address(), non_null.get_address());
+}
+
+struct Entry {
+    value: u32,
+    free: Option<IntrusiveAlias>,
+    lru: Option<IntrusiveAlias>,
+}
+containerof_intrusive!(EntryFree = Entry:free::Option<IntrusiveAlias>);
+containerof_intrusive!(EntryLru = Entry:lru::Option<IntrusiveAlias>);
+
+impl LinkInit for Entry {
+    fn init_links(&mut self) {
+        self.free.init_links();
+        self.lru.init_links();
+    }
+}
+
+fn walk<T: Intrusive<Container = Entry, Field = Option<IntrusiveAlias>>>(
+    head: IntrusiveAlias,
+) -> Vec<u32> {
+    let mut values = Vec::new();
+    let mut next = Some(head);
+    while let Some(alias) = next {
+        let link = unsafe { T::of_alias(&alias) };
+        values.push(link.as_container().value);
+        next = *link.as_field();
+    }
+    values
+}
+
+struct Chain<'a, T> {
+    link: *const Option<IntrusiveAlias>,
+    entries: &'a [Entry],
+    marker: ::std::marker::PhantomData<T>,
+}
+impl<'a, T> Iterator for Chain<'a, T>
+where
+    T: Intrusive<Container = Entry, Field = Option<IntrusiveAlias>> + 'a,
+{
+    type Item = &'a Entry;
+    fn next(&mut self) -> Option<&'a Entry> {
+        let alias = unsafe { (*self.link).as_ref()? };
+        let link: &'a T = unsafe { T::of_alias_lt(alias, self.entries) };
+        self.link = link.as_field();
+        Some(link.as_container())
+    }
+}
+
+#[test]
+fn test_of_alias_lt() {
+    let mut entries: Vec<Entry> = (0..3)
+        .map(|value| Entry {
+            value,
+            free: None,
+            lru: None,
+        })
+        .collect();
+    let base = entries.as_mut_ptr();
+    let free_of = move |i| {
+        IntrusiveAlias::new(base as *const ())
+            .byte_add(i * ::std::mem::size_of::<Entry>() + EntryFree::offset())
+    };
+    unsafe {
+        (*base).free = Some(free_of(2));
+        (*base.add(2)).free = Some(free_of(1));
+    }
+    let head = Some(free_of(0));
+    let chain: Chain<EntryFree> = Chain {
+        link: &head,
+        entries: &entries,
+        marker: ::std::marker::PhantomData,
+    };
+    let collected: Vec<&Entry> = chain.collect();
+    assert_eq!(
+        vec![0, 2, 1],
+        collected.iter().map(|e| e.value).collect::<Vec<_>>()
+    );
+    assert!(::std::ptr::eq(&entries[2], collected[1]));
+}
+
+#[test]
+fn test_container_in_two_chains() {
+    let new_entry = |value| {
+        Box::into_raw(Box::new(Entry {
+            value,
+            free: None,
+            lru: None,
+        }))
+    };
+    let free_of = |e: *mut Entry| IntrusiveAlias::new(e as *const ()).byte_add(EntryFree::offset());
+    let lru_of = |e: *mut Entry| IntrusiveAlias::new(e as *const ()).byte_add(EntryLru::offset());
+    let first = new_entry(1);
+    let second = new_entry(2);
+
+    // free chain: first -> second. lru chain: second -> first.
+    unsafe {
+        (*first).free = Some(free_of(second));
+        (*second).lru = Some(lru_of(first));
+    }
+    assert_eq!(vec![1, 2], walk::<EntryFree>(free_of(first)));
+    assert_eq!(vec![2, 1], walk::<EntryLru>(lru_of(second)));
+
+    // unlink second from the free chain; it stays in the lru chain.
+    let mut first_free = free_of(first);
+    unsafe { <EntryFree as Intrusive>::of_alias_mut(&mut first_free) }
+        .as_field_mut()
+        .take();
+    assert_eq!(vec![1], walk::<EntryFree>(free_of(first)));
+    assert_eq!(vec![2], walk::<EntryFree>(free_of(second)));
+    assert_eq!(vec![2, 1], walk::<EntryLru>(lru_of(second)));
+
+    unsafe {
+        drop(Box::from_raw(first));
+        drop(Box::from_raw(second));
+    }
+}
+
+#[test]
+fn test_replace_container() {
+    let new_entry = |value| {
+        Box::into_raw(Box::new(Entry {
+            value,
+            free: None,
+            lru: None,
+        }))
+    };
+    let free_of = |e: *mut Entry| IntrusiveAlias::new(e as *const ()).byte_add(EntryFree::offset());
+    let first = new_entry(1);
+    let second = new_entry(2);
+    unsafe { (*first).free = Some(free_of(second)) };
+
+    let mut second_free = free_of(second);
+    let link = unsafe { <EntryFree as Intrusive>::of_alias_mut(&mut second_free) };
+    let replacement = Entry {
+        value: 20,
+        free: link.as_container().free,
+        lru: link.as_container().lru,
+    };
+    let old = link.replace_container(replacement);
+    assert_eq!(2, old.value);
+    assert_eq!(vec![1, 20], walk::<EntryFree>(free_of(first)));
+
+    unsafe {
+        drop(Box::from_raw(first));
+        drop(Box::from_raw(second));
+    }
+}
+
+#[test]
+fn test_detect_cycle() {
+    let mut entries: Vec<Entry> = (0..3)
+        .map(|value| Entry {
+            value,
+            free: None,
+            lru: None,
+        })
+        .collect();
+    let base = entries.as_mut_ptr();
+    let free_of = move |i| {
+        IntrusiveAlias::new(base as *const ())
+            .byte_add(i * ::std::mem::size_of::<Entry>() + EntryFree::offset())
+    };
+    fn next(e: &Entry) -> Option<&IntrusiveAlias> {
+        e.free.as_ref()
+    }
+    unsafe {
+        (*base).free = Some(free_of(1));
+        (*base.add(1)).free = Some(free_of(2));
+        assert!(!validate::detect_cycle::<EntryFree, _>(&free_of(0), next));
+        assert!(!validate::detect_cycle::<EntryFree, _>(&free_of(2), next));
+
+        (*base.add(2)).free = Some(free_of(0));
+        assert!(validate::detect_cycle::<EntryFree, _>(&free_of(0), next));
+        assert!(validate::detect_cycle::<EntryFree, _>(&free_of(1), next));
+    }
+}
+
+#[test]
+fn test_init_links() {
+    use std::mem::MaybeUninit;
+    use std::ptr;
+
+    // a recycled slot: the payload is fresh, but the links are stale.
+    let stale = Some(IntrusiveAlias::new(0xdead as *const ()));
+    let mut slot = MaybeUninit::<Entry>::uninit();
+    let node = unsafe {
+        let p = slot.as_mut_ptr();
+        ptr::addr_of_mut!((*p).value).write(2);
+        ptr::addr_of_mut!((*p).free).write(stale);
+        ptr::addr_of_mut!((*p).lru).write(stale);
+        &mut *p
+    };
+    node.init_links();
+    assert!(node.free.is_none());
+    assert!(node.lru.is_none());
+
+    let mut head = Entry {
+        value: 1,
+        free: None,
+        lru: None,
+    };
+    head.free = Some(*EntryFree::of_container(node).as_alias());
+    assert_eq!(
+        vec![1, 2],
+        walk::<EntryFree>(*EntryFree::of_container(&head).as_alias())
+    );
+}
+
+#[test]
+fn test_project() {
+    let entry = Entry {
+        value: 1,
+        free: None,
+        lru: None,
+    };
+    let entry_addr = &entry as *const Entry as usize;
+    let free = <EntryFree as Intrusive>::of_container(&entry);
+    let lru = free.project::<EntryLru>();
+    assert_eq!(
+        entry_addr + containerof_field_offset!(Entry: lru),
+        lru.as_alias().get_address() as usize
+    );
+    assert_eq!(1, lru.as_container().value);
+    let free_again = lru.project::<EntryFree>();
+    assert_eq!(free.as_alias(), free_again.as_alias());
+}
+
+#[test]
+fn test_container_ptr() {
+    let mc: OwnBox<_> = OwnBox::new(MyStruct {
+        field1: 1,
+        field2: 2,
+        field3: 3,
+    });
+    let mc_addr = mc.as_ptr();
+    let mut mcfield: MyStructField2_Meth1 = Intrusive::from_container(mc);
+    assert_eq!(mc_addr, mcfield.container_ptr());
+    assert_eq!(mc_addr as *mut MyStruct, mcfield.container_ptr_mut());
+    unsafe { (*mcfield.container_ptr_mut()).field1 = 10 };
+    assert_eq!(10, mcfield.as_container().field1);
+
+    let _ = unsafe { mcfield.into_container().into_box() };
+}
+
+fn entry_value<C: AsRef<Entry>>(c: &C) -> u32 {
+    c.as_ref().value
+}
+
+#[test]
+fn test_as_ref_as_mut() {
+    let entry = OwnBox::new(Entry {
+        value: 1,
+        free: None,
+        lru: None,
+    });
+    let mut free: EntryFree = Intrusive::from_container(entry);
+    assert_eq!(1, entry_value(&free));
+    AsMut::<Entry>::as_mut(&mut free).value = 2;
+    assert_eq!(2, entry_value(&free));
+
+    let field: &Option<IntrusiveAlias> = free.as_ref();
+    assert!(field.is_none());
+    let alias = *free.as_alias();
+    *AsMut::<Option<IntrusiveAlias>>::as_mut(&mut free) = Some(alias);
+    assert_eq!(Some(alias), free.as_container().free);
+
+    let _ = unsafe { free.into_container().into_box() };
+}
+
+#[test]
+fn test_from_own_box() {
+    let entry = OwnBox::new(Entry {
+        value: 1,
+        free: None,
+        lru: None,
+    });
+    let entry_addr = entry.get_address();
+    let free: EntryFree = entry.into();
+    assert_eq!(entry_addr, free.container_ptr() as *const ());
+    let entry: OwnBox<Entry> = free.into();
+    assert_eq!(entry_addr, entry.get_address());
+
+    let _ = unsafe { entry.into_box() };
+}
+
+#[test]
+fn test_borrow_box_copy() {
+    let mc = MyStruct {
+        field1: 1,
+        field2: 2,
+        field3: 3,
+    };
+    let other = mc.clone();
+    let mcfield = <MyStructField2_Meth1 as Intrusive>::of_container(&mc);
+    let copy = mcfield;
+    let clone = Clone::clone(&copy);
+    assert!(mcfield == copy);
+    assert!(copy == clone);
+    assert_eq!(2, *copy.as_field());
+    assert_eq!(2, *mcfield.as_field());
+
+    let otherfield = <MyStructField2_Meth1 as Intrusive>::of_container(&other);
+    assert!(mcfield != otherfield);
+}
+
+fn set_field2(mut mcfield: BorrowBoxMut<MyStructField2_Meth1>, value: i32) {
+    *mcfield.as_field_mut() = value;
+}
+
+#[test]
+fn test_borrow_box_mut_reborrow() {
+    let mut mc = MyStruct {
+        field1: 1,
+        field2: 2,
+        field3: 3,
+    };
+    {
+        let mut mcfield = <MyStructField2_Meth1 as Intrusive>::of_container_mut(&mut mc);
+        set_field2(mcfield.reborrow(), 20);
+        assert_eq!(20, *mcfield.as_field());
+        set_field2(mcfield.reborrow(), 30);
+        {
+            let shared = mcfield.as_borrow();
+            assert_eq!(30, *shared.as_field());
+            assert_eq!(1, shared.as_container().field1);
+        }
+        mcfield.as_container_mut().field1 = 10;
+    }
+    assert_eq!(10, mc.field1);
+    assert_eq!(30, mc.field2);
+}
+
+struct Triple {
+    a: u32,
+    b: u64,
+    c: u16,
+}
+containerof_intrusive!(TripleA = Triple:a::u32);
+containerof_intrusive!(TripleB = Triple:b::u64);
+containerof_intrusive!(TripleC = Triple:c::u16);
+
+impl Triple {
+    fn sum(&self) -> u64 {
+        u64::from(self.a) + self.b + u64::from(self.c)
+    }
+}
+
+#[test]
+fn test_borrow_box_map_field() {
+    let triple = Triple { a: 1, b: 2, c: 3 };
+    let b: BorrowBox<TripleB> = {
+        let a = <TripleA as Intrusive>::of_container(&triple);
+        a.map_field()
+    };
+    let c = b.map_field::<TripleC>();
+    assert_eq!(2, *b.as_field());
+    assert_eq!(3, *c.as_field());
+    assert_eq!(&triple.b as *const u64, b.as_field() as *const u64);
+    assert_eq!(&triple.c as *const u16, c.as_field() as *const u16);
+    assert_eq!(1, c.as_container().a);
+}
+
+#[test]
+fn test_intrusive_alias_addr() {
+    let value = 5u64;
+    let alias = IntrusiveAlias::new_of(&value);
+    assert_eq!(&value as *const u64 as usize, alias.addr());
+    assert_eq!(alias.addr() + 3, alias.with_tag::<u64>(3).addr());
+    assert_eq!(0, IntrusiveAlias::null().addr());
+}
+
+#[test]
+fn test_ownbox_clone() {
+    let original = OwnBox::new(vec![1, 2, 3]);
+    let copy = original.clone();
+    assert!(original != copy);
+    assert_eq!(*original, *copy);
+    let _ = unsafe { original.into_box() };
+    let _ = unsafe { copy.into_box() };
+}
+
+#[test]
+fn test_ownbox_rc_arc() {
+    let rc = unsafe { OwnBox::new(String::from("shared")).into_rc() };
+    let other = rc.clone();
+    let rc = OwnBox::from_rc(rc).unwrap_err();
+    drop(other);
+    let ob = OwnBox::from_rc(rc).unwrap();
+    assert_eq!("shared", &*ob);
+
+    let arc = unsafe { ob.into_arc() };
+    let other = arc.clone();
+    let arc = OwnBox::from_arc(arc).unwrap_err();
+    drop(other);
+    let ob = OwnBox::from_arc(arc).unwrap();
+    assert_eq!("shared", &*ob);
+    let _ = unsafe { ob.into_box() };
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "is not aligned for the target type")]
+fn test_ownbox_from_alias_misaligned() {
+    let value = 5u64;
+    let misaligned = IntrusiveAlias::new_of(&value).byte_add(1);
+    let _ = unsafe { OwnBox::<u64>::from_alias(misaligned).into_alias() };
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "is not aligned for the container type")]
+fn test_intrusive_from_alias_misaligned() {
+    let triple = Triple { a: 1, b: 2, c: 3 };
+    let misaligned = IntrusiveAlias::new_of(&triple.b).byte_add(2);
+    let _ = unsafe { TripleB::from_alias(misaligned).into_alias() };
+}
+
+#[test]
+fn test_container_ptr_round_trip() {
+    let p = Box::into_raw(Box::new(Triple { a: 1, b: 2, c: 3 }));
+    let link = unsafe { TripleB::from_container_ptr(p) };
+    assert_eq!(2, *link.as_field());
+    assert_eq!(p, link.into_container_ptr());
+    let _ = unsafe { Box::from_raw(p) };
+}
+
+#[test]
+fn test_container_view() {
+    let ob = OwnBox::new(Triple { a: 1, b: 2, c: 3 });
+    let mut view = ContainerView(TripleC::from_container(ob));
+    assert_eq!(6, view.sum());
+    view.a = 10;
+    assert_eq!(15, view.sum());
+    let _ = unsafe { view.into_inner().into_container().into_box() };
+}
+
+#[test]
+fn test_intrusive_alias_non_null() {
+    use std::convert::TryInto;
+    use std::ptr::NonNull;
+
+    let mut value = 5u32;
+    let pointer = NonNull::from(&mut value);
+    let alias = IntrusiveAlias::from(pointer);
+    assert_eq!(pointer.as_ptr() as *const u32, alias.cast::<u32>());
+    assert_eq!(pointer.as_ptr(), alias.cast_mut::<u32>());
+    let back: NonNull<u32> = alias.try_into().unwrap();
+    assert_eq!(pointer, back);
+    let null: Result<NonNull<u32>, _> = IntrusiveAlias::null().try_into();
+    assert_eq!(Err(NullAliasError), null);
+}
+
+#[test]
+fn test_weak_alias() {
+    let triple = Triple { a: 1, b: 2, c: 3 };
+    let weak = WeakAlias::<TripleB>::of_container(&triple);
+    let copy = weak;
+    assert_eq!(weak, copy);
+    let link = unsafe { weak.upgrade(&triple) };
+    assert_eq!(2, *link.as_field());
+    assert_eq!(6, link.as_container().sum());
+    assert_eq!(weak, WeakAlias::from_alias(*link.as_alias()));
+
+    let triples = [Triple { a: 4, b: 5, c: 6 }];
+    let weak = WeakAlias::<TripleB>::of_container(&triples[0]);
+    let link = unsafe { weak.upgrade(&triples[..]) };
+    assert_eq!(5, *link.as_field());
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "extends past the end of its container")]
+fn test_field_out_of_bounds_mut() {
+    let mut ms = MyStruct {
+        field1: 1,
+        field2: 2,
+        field3: 3,
+    };
+    let _ = MyStructField3_Bogus::of_container_mut(&mut ms);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "extends past the end of its container")]
+fn test_field_out_of_bounds_ptr() {
+    let mut ms = MyStruct {
+        field1: 1,
+        field2: 2,
+        field3: 3,
+    };
+    let _ = unsafe { MyStructField3_Bogus::from_container_ptr(&mut ms) };
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "extends past the end of its container")]
+fn test_field_out_of_bounds_weak() {
+    let ms = MyStruct {
+        field1: 1,
+        field2: 2,
+        field3: 3,
+    };
+    let _ = WeakAlias::<MyStructField3_Bogus>::of_container(&ms);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "extends past the end of its container")]
+fn test_field_out_of_bounds() {
+    let ms = MyStruct {
+        field1: 1,
+        field2: 2,
+        field3: 3,
+    };
+    let _ = MyStructField3_Bogus::of_container(&ms);
+}
+
+#[test]
+fn test_reclaim() {
+    use std::rc::Rc;
+
+    let counter = Rc::new(());
+    {
+        let mut guard = unsafe { Reclaim::new(OwnBox::new(counter.clone())) };
+        assert_eq!(2, Rc::strong_count(&*guard));
+        *guard = counter.clone();
+        assert_eq!(2, Rc::strong_count(&counter));
+    }
+    assert_eq!(1, Rc::strong_count(&counter));
+
+    let guard = unsafe { Reclaim::new(OwnBox::new(counter.clone())) };
+    let ob = guard.into_inner();
+    assert_eq!(2, Rc::strong_count(&counter));
+    let _ = unsafe { ob.into_box() };
+    assert_eq!(1, Rc::strong_count(&counter));
+
+    // an OwnBox rebuilt from an intrusive pointer is just as valid.
+    let link = TripleA::from_container(OwnBox::new(Triple { a: 1, b: 2, c: 3 }));
+    let guard = unsafe { Reclaim::new(link.into_container()) };
+    assert_eq!(6, guard.sum());
+}
+
+#[test]
+fn test_raw_pointer_accessors() {
+    let ob = OwnBox::new(Triple { a: 1, b: 2, c: 3 });
+    let mut link = TripleC::from_container(ob);
+    assert_eq!(link.as_field() as *const u16, link.as_field_ptr());
+    assert_eq!(link.as_field_mut() as *mut u16, link.as_field_ptr_mut());
+    assert_eq!(link.as_container() as *const Triple, link.container_ptr());
+    assert_eq!(
+        link.as_container_mut() as *mut Triple,
+        link.container_ptr_mut()
+    );
+    unsafe { *link.as_field_ptr_mut() = 30 };
+    assert_eq!(30, link.as_container().c);
+    let _ = unsafe { link.into_container().into_box() };
+}
+
+#[repr(C)]
+struct Headed {
+    link: Option<IntrusiveAlias>,
+    value: u32,
+}
+containerof_intrusive!(HeadedLink = Headed:link::Option<IntrusiveAlias>);
+
+#[test]
+fn test_zero_offset() {
+    assert_eq!(0, HeadedLink::offset());
+    let ob = OwnBox::new(Headed {
+        link: None,
+        value: 7,
+    });
+    let address = ob.get_address();
+    let link = HeadedLink::from_container(ob);
+    assert_eq!(address, link.as_alias().get_address());
+    assert_eq!(address, link.as_container() as *const Headed as *const ());
+    assert_eq!(7, link.as_container().value);
+    let _ = unsafe { link.into_container().into_box() };
+}
+
+#[repr(C)]
+#[cfg_attr(not(debug_assertions), allow(dead_code))]
+struct Pair {
+    x: u32,
+    y: u32,
+    z: u64,
+}
+
+// Deliberately wrong: `y` is a `u32` at offset 4, which is not
+// aligned for the declared `u64`.
+#[allow(non_camel_case_types, dead_code)]
+struct PairY_Bogus(*const ());
+impl IntrusiveBase for PairY_Bogus {
+    type Container = Pair;
+    type Field = u64;
+
+    fn offset() -> usize {
+        containerof_field_offset!(Pair:y)
+    }
+    unsafe fn new(ia: IntrusiveAlias) -> Self {
+        PairY_Bogus(ia.get_address())
+    }
+    fn as_alias(&self) -> &IntrusiveAlias {
+        unsafe { &*(self as *const _ as *const IntrusiveAlias) }
+    }
+    fn as_alias_mut(&mut self) -> &mut IntrusiveAlias {
+        unsafe { &mut *(self as *mut _ as *mut IntrusiveAlias) }
+    }
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "is not aligned for the field type")]
+fn test_into_field_misaligned() {
+    let pair = Pair { x: 1, y: 2, z: 3 };
+    let link = unsafe { PairY_Bogus::from_alias(IntrusiveAlias::new_of(&pair.y)) };
+    let _ = link.into_field().into_alias();
+}
+
+#[test]
+fn test_same_container() {
+    let mut first = Triple { a: 1, b: 2, c: 3 };
+    let second = Triple { a: 1, b: 2, c: 3 };
+    let first_a = TripleA::of_container(&first);
+    let first_c = TripleC::of_container(&first);
+    let second_a = TripleA::of_container(&second);
+    assert!(first_a.same_container(&*first_c));
+    assert!(first_c.same_container(&*first_a));
+    assert!(!first_a.same_container(&*second_a));
+
+    let mut other = Triple { a: 1, b: 2, c: 3 };
+    let first_b = TripleB::of_container_mut(&mut first);
+    let other_b = TripleB::of_container_mut(&mut other);
+    assert!(first_b != other_b);
+    assert!(!first_b.same_container(&*other_b));
+}
+
+#[test]
+fn test_alias_move_round_trip() {
+    let link = TripleB::from_container(OwnBox::new(Triple { a: 1, b: 2, c: 3 }));
+    let address = link.as_alias().get_address();
+    let alias = unsafe { link.into_alias() };
+    assert_eq!(address, alias.get_address());
+    let link = unsafe { TripleB::from_alias(alias) };
+    assert_eq!(address, link.as_alias().get_address());
+    assert_eq!(2, *link.as_field());
+    let _ = unsafe { link.into_container().into_box() };
+}
+
+#[test]
+fn test_ownbox_cast() {
+    let word = OwnBox::new(u32::from_ne_bytes([1, 2, 3, 4]));
+    let bytes: OwnBox<[u8; 4]> = unsafe { word.cast() };
+    assert_eq!([1, 2, 3, 4], *bytes);
+    let word: OwnBox<u32> = unsafe { bytes.cast() };
+    assert_eq!(u32::from_ne_bytes([1, 2, 3, 4]), *word);
+    let _ = unsafe { word.into_box() };
+}
+
+#[test]
+fn test_borrow_box_raw() {
+    fn callback(context: usize, triple: &Triple) -> u64 {
+        let borrow = unsafe { BorrowBox::<TripleB>::from_raw(context as *const (), triple) };
+        *borrow.as_field()
+    }
+    let triple = Triple { a: 1, b: 2, c: 3 };
+    let borrow = TripleB::of_container(&triple);
+    let context = borrow.as_raw() as usize;
+    assert_eq!(2, callback(context, &triple));
+
+    // any borrow that outlives the pointee will do, sized or not.
+    let triples = [Triple { a: 4, b: 5, c: 6 }];
+    let context = TripleB::of_container(&triples[0]).as_raw();
+    let borrow = unsafe { BorrowBox::<TripleB>::from_raw(context, &triples[..]) };
+    assert_eq!(5, *borrow.as_field());
+}
+
+#[test]
+fn test_cmp_by() {
+    use std::cmp::Ordering;
+
+    let low = Triple { a: 1, b: 2, c: 3 };
+    let high = Triple { a: 1, b: 5, c: 0 };
+    let low_c = TripleC::of_container(&low);
+    let high_c = TripleC::of_container(&high);
+    assert_eq!(Ordering::Less, low_c.cmp_by(&high_c, |t| &t.b));
+    assert_eq!(Ordering::Greater, low_c.cmp_by(&high_c, |t| &t.c));
+    assert_eq!(Ordering::Equal, low_c.cmp_by(&high_c, |t| &t.a));
+}
+
+#[test]
+fn test_of_field_lt() {
+    let triples = [Triple { a: 1, b: 2, c: 3 }, Triple { a: 4, b: 5, c: 6 }];
+    let links: Vec<BorrowBox<TripleB>> = triples
+        .iter()
+        .map(|t| {
+            // the field borrow ends here; the result borrows `triples`.
+            let field: *const u64 = &t.b;
+            unsafe { TripleB::of_field_lt(field, &triples[..]) }
+        })
+        .collect();
+    assert_eq!(5, *links[1].as_field());
+    assert_eq!(4, links[1].as_container().a);
+}
+
+#[test]
+fn test_layouts() {
+    use std::alloc::Layout;
+
+    assert_eq!(Layout::new::<Triple>(), TripleC::container_layout());
+    assert_eq!(Layout::new::<u16>(), TripleC::field_layout());
+    assert!(
+        TripleC::offset() + TripleC::field_layout().size() <= TripleC::container_layout().size()
+    );
+}
+
+#[test]
+fn test_intrusive_try_from_alias() {
+    assert!(unsafe { TripleB::try_from_alias(IntrusiveAlias::null()) }.is_none());
+
+    let link = TripleB::from_container(OwnBox::new(Triple { a: 1, b: 2, c: 3 }));
+    let alias = unsafe { link.into_alias() };
+    let link = unsafe { TripleB::try_from_alias(alias) }.unwrap();
+    assert_eq!(alias, *link.as_alias());
+    let _ = unsafe { link.into_container().into_box() };
+}
+
+#[test]
+fn test_ownbox_uninit() {
+    use std::mem::MaybeUninit;
+
+    let slot = OwnBox::new(MaybeUninit::<Triple>::uninit());
+    let address = slot.get_address();
+    let triple = slot.write(Triple { a: 1, b: 2, c: 3 });
+    assert_eq!(address, triple.get_address());
+    assert_eq!(6, triple.sum());
+    let _ = unsafe { triple.into_box() };
+
+    let mut slot = OwnBox::new(MaybeUninit::<u32>::uninit());
+    unsafe { slot.as_mut_ptr().cast::<u32>().write(7) };
+    let value = unsafe { slot.assume_init() };
+    assert_eq!(7, *value);
+    let _ = unsafe { value.into_box() };
+}
+
+#[test]
+fn test_detach() {
+    let entry = OwnBox::new(Entry {
+        value: 1,
+        free: None,
+        lru: None,
+    });
+    let link = EntryFree::from_container(entry);
+    let entry = link.detach(|e| e.free.is_none() && e.lru.is_none());
+    assert_eq!(1, entry.value);
+    let _ = unsafe { entry.into_box() };
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "container is still linked")]
+fn test_detach_linked() {
+    let other = Entry {
+        value: 2,
+        free: None,
+        lru: None,
+    };
+    let mut entry = Entry {
+        value: 1,
+        free: Some(*EntryFree::of_container(&other).as_alias()),
+        lru: None,
+    };
+    let link = unsafe { EntryFree::from_container_ptr(&mut entry) };
+    let _ = link
+        .detach(|e| e.free.is_none() && e.lru.is_none())
+        .into_alias();
+}
@@ -0,0 +1,36 @@
+//! Build check for `containerof` with `default-features = false`:
+//! building this crate proves the core of `containerof` works in a
+//! `no_std` environment. Build with
+//! `cargo build --manifest-path tests/no_std/Cargo.toml`.
+#![no_std]
+
+extern crate containerof;
+use containerof::*;
+
+pub struct Link {
+    pub next: Option<ContainerLink>,
+}
+
+pub struct Container {
+    pub value: u32,
+    pub link: Link,
+}
+containerof_intrusive!(pub ContainerLink = Container:link::Link);
+
+pub fn link_offset() -> usize {
+    containerof_field_offset!(Container: link)
+}
+
+pub fn value_of(container: &Container) -> u32 {
+    let link = <ContainerLink as Intrusive>::of_container(container);
+    link.as_container().value
+}
+
+pub fn set_value(container: &mut Container, value: u32) {
+    let mut link = <ContainerLink as Intrusive>::of_container_mut(container);
+    link.as_container_mut().value = value;
+}
+
+pub fn container_address(link: &Link) -> *const Container {
+    unsafe { container_of!(link as *const Link, Container, link) }
+}